@@ -65,40 +65,24 @@ pub fn image_to_tiles<P: AsRef<Path>>(path: P) -> Array2D<LOStemContent> {
     );
 
     let room_template = LOStemContent::TileMapEdit {
+        unknown1: 0,
         id: 0,
         name: "untitled".into(),
         width: 24,
         height: 16,
+        unknown2: 0,
+        metadata: LOTileMapMetadata::default(),
         layers: vec![
             // Floor
-            LOLayer {
-                width: 24,
-                height: 16,
-                tiles: vec![LOTile::Space; 24*16],
-            },
-            LOLayer {
-                width: 24,
-                height: 16,
-                tiles: vec![LOTile::None; 24*16],
-            },
-            LOLayer {
-                width: 24,
-                height: 16,
-                tiles: vec![LOTile::None; 24*16],
-            },
-            LOLayer {
-                width: 24,
-                height: 16,
-                tiles: vec![LOTile::None; 24*16],
-            },
-            LOLayer {
-                width: 24,
-                height: 16,
-                tiles: vec![LOTile::None; 24*16],
-            },
+            LOLayer::new(24, 16, vec![LOTile::Space; 24*16]),
+            LOLayer::new(24, 16, vec![LOTile::None; 24*16]),
+            LOLayer::new(24, 16, vec![LOTile::None; 24*16]),
+            LOLayer::new(24, 16, vec![LOTile::None; 24*16]),
+            LOLayer::new(24, 16, vec![LOTile::None; 24*16]),
         ],
         music: LOMusic::None,
         revision: 1,
+        extra_fields: vec![],
     };
     let mut rooms = Array2D::filled_with(room_template, room_count.1 as usize, room_count.0 as usize);
 
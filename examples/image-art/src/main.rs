@@ -4,7 +4,7 @@ mod image_to_tiles;
 
 use std::{env, error::Error, path::PathBuf, process::exit};
 
-use leafs_odyssey_data::{data::*, io::get_worlds_folder};
+use leafs_odyssey_data::{data::*, guid::*, io::get_worlds_folder};
 
 use crate::image_to_tiles::*;
 
@@ -28,42 +28,27 @@ fn main() -> Result<(), Box<dyn Error>> {
 
     let room_coords = rooms.indices_row_major()
         .enumerate()
-        .map(|(i, (y, x))| LORoomInfo {
-            id: (i+1) as u32,
-            width: 24,
-            height: 16,
-            x_position: (x * 24) as i16,
-            y_position: (y * 16) as i16,
-            z_position: 0,
-        })
+        .map(|(i, (y, x))| LORoomInfo::new((i+1) as u32, ((x * 24) as i16, (y * 16) as i16, 0), 24, 16))
         .collect::<Vec<_>>();
 
-    let world_guids = random_guid(3);
-    let world_guids = parse_guid(&world_guids).unwrap();
-
     let mut world = LOWorld::new(
-        LOZone {
-            stem_offset: 0,
-            stem_length: 0,
-            rooms: vec![
-                LORoom {
-                    stem_offset: 0,
-                    stem_length: 0,
-                }; room_coords.len()
-            ],
-        },
+        LOZone::new(vec![LORoom::default(); room_coords.len()]),
         velcro::vec![
             LOStem::from_content(LOStemContent::TileZoneMap {
+                unknown1: 0,
+                id: 1,
                 name: "[Generated] Image Art".into(),
+                metadata: LOZoneMetadata::default(),
                 room_info: room_coords,
+                listed_room_count: None,
                 description: "(no description provided)".into(),
                 author: "Rust".into(),
-                guid_world: world_guids[0],
-                guid_author1: world_guids[1],
-                guid_author2: world_guids[2],
+                guid_world: WorldGuid::new_random(),
+                guid_author: AuthorGuid::new_random(),
                 world_revision: 1,
                 start_room: 1,
                 compatibility: 0,
+                extra_fields: vec![],
             }),
             ..rooms.as_row_major()
                 .into_iter()
@@ -83,10 +68,8 @@ fn main() -> Result<(), Box<dyn Error>> {
     }
     println!("Writing file \"{:?}\"...", world_path);
 
-    unsafe {
-        let mut fa = std::fs::File::create(world_path)?;
-        world.write_world(&mut fa)?;
-    }
+    let mut fa = std::fs::File::create(world_path)?;
+    world.write_world(&mut fa)?;
 
     Ok(())
 }
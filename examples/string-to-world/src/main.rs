@@ -4,7 +4,7 @@ mod string_parser;
 
 use std::{env, error::Error, path::PathBuf, process::exit};
 
-use leafs_odyssey_data::{builder::*, data::*, io::get_worlds_folder};
+use leafs_odyssey_data::{builder::*, data::*, guid::*, io::get_worlds_folder};
 
 use crate::string_parser::*;
 
@@ -24,7 +24,7 @@ fn main() -> Result<(), Box<dyn Error>> {
 
     let mut world = World::new()
         .with_metadata("[Generated] String", "Yup!")
-        .with_identity(&random_guid_segment(), Author::new("Rust", "00000000-FFFFFFFF"));
+        .with_identity(WorldGuid::new_random(), Author::new("Rust", "00000000-FFFFFFFF".parse()?));
     
     import_string(&args[0], &mut world)?;
 
@@ -40,11 +40,12 @@ fn main() -> Result<(), Box<dyn Error>> {
     }
     println!("Writing file \"{:?}\"...", world_path);
 
-    unsafe {
-        let mut fa = std::fs::File::create(world_path)?;
-        let mut world = LOWorld::try_from(world)?;
-        world.write_world(&mut fa)?;
+    for warning in world.warnings() {
+        println!("WARNING: {}", warning);
     }
+    let mut fa = std::fs::File::create(world_path)?;
+    let mut world = LOWorld::try_from(world)?;
+    world.write_world(&mut fa)?;
 
     Ok(())
 }
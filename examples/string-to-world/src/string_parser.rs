@@ -78,10 +78,10 @@ pub fn import_string<P: AsRef<Path>>(path: P, world: &mut World) -> Result<(), B
 
         let mut x = 0;
         let mut y = 0;
-        let selection = room.tilemap.select();
         for tile_stack in &layer.tile_stacks {
+            let selection = room.tilemap.select().add(x, y);
             for tile in tile_stack {
-                room.tilemap.write(&tile, &selection.clone().add(x, y));
+                room.tilemap.write(&tile, &selection)?;
             }
             x += 1;
             if x >= layer.width.unwrap() {
@@ -92,7 +92,7 @@ pub fn import_string<P: AsRef<Path>>(path: P, world: &mut World) -> Result<(), B
                 break;
             }
         }
-        world.rooms.push(room);
+        world.add_room(room);
     }
 
     Ok(())
@@ -29,13 +29,17 @@ pub fn generate_terrain() -> Terrain2D {
 
 pub fn terrain_to_rooms(terrain: Terrain2D, room_count: (usize, usize)) -> Array2D<LOStemContent> {
     let room_template = LOStemContent::TileMapEdit {
+        unknown1: 0,
         id: 0,
         name: "".into(),
         width: 24,
         height: 16,
+        unknown2: 0,
+        metadata: LOTileMapMetadata::default(),
         layers: vec![],
         music: LOMusic::None,
         revision: 1,
+        extra_fields: vec![],
     };
     let mut rooms = Array2D::filled_with(room_template, room_count.1, room_count.0);
 
@@ -64,27 +68,19 @@ pub fn terrain_to_rooms(terrain: Terrain2D, room_count: (usize, usize)) -> Array
             *name = format!("Room {}x{}x0", room_x, room_y).into();
             layers.append(&mut vec![
                 // Floor
-                LOLayer {
-                    width: 24,
-                    height: 16,
-                    tiles: tiles.elements_row_major_iter()
+                LOLayer::new(
+                    24,
+                    16,
+                    tiles.elements_row_major_iter()
                         .map(|elevation| elevation_to_tile(*elevation))
                         .collect(),
-                },
-                LOLayer {
-                    width: 24,
-                    height: 16,
-                    tiles: vec![LOTile::None; 24*16],
-                },
-                LOLayer {
-                    width: 24,
-                    height: 16,
-                    tiles: vec![LOTile::None; 24*16],
-                },
-                LOLayer {
-                    width: 24,
-                    height: 16,
-                    tiles: vec![LOTile::None; 24*16]
+                ),
+                LOLayer::new(24, 16, vec![LOTile::None; 24*16]),
+                LOLayer::new(24, 16, vec![LOTile::None; 24*16]),
+                LOLayer::new(
+                    24,
+                    16,
+                    vec![LOTile::None; 24*16]
                         .iter()
                         .enumerate()
                         .map(|(i, tile)| {
@@ -97,12 +93,8 @@ pub fn terrain_to_rooms(terrain: Terrain2D, room_count: (usize, usize)) -> Array
                             }
                         })
                         .collect(),
-                },
-                LOLayer {
-                    width: 24,
-                    height: 16,
-                    tiles: vec![LOTile::None; 24*16],
-                },
+                ),
+                LOLayer::new(24, 16, vec![LOTile::None; 24*16]),
             ]);
         }
     }
@@ -1,21 +1,83 @@
-use array2d::Array2D;
+use std::collections::{HashMap, HashSet};
 
-use crate::data::*;
+use array2d::Array2D;
 
-const INVALID_GUID: &'static str = "00000000";
+use crate::{
+    data::*,
+    error::LOError,
+    guid::{AuthorGuid, WorldGuid},
+    validate::LODiagnostic,
+};
+
+mod cell;
+mod connections;
+mod content;
+mod journal;
+mod morphology;
+mod selection;
+mod shapes;
+mod spatial;
+mod stack;
+mod stamp;
+mod transform;
+mod world_space;
+
+pub use cell::TileCell;
+pub use connections::TileConnection;
+pub use content::Connectivity;
+pub use journal::{Journal, TileEdit, Transaction};
+pub use morphology::Edge;
+pub use selection::{SelectionBackend, TileSelection};
+pub use spatial::Direction;
+pub use stack::StackBuilder;
+pub use stamp::Stamp;
+pub use transform::Transform;
+pub use world_space::WorldSelection;
 
 pub struct World {
     pub name: String,
     pub description: String,
     pub author: Author,
-    pub guid: String,
+    pub guid: WorldGuid,
     pub revision: u32,
     pub rooms: Vec<Room>,
     /// Default width for rooms. Used to compute room position in the world.
     pub room_width: u16,
     /// Default height for rooms. Used to compute room position in the world.
     pub room_height: u16,
+    /// Id of the room the player starts in. If `None`, the room containing the start point is used.
+    pub start_room: Option<u32>,
     last_room_id: u32,
+    preserved: Option<PreservedZone>,
+    journal: Option<Journal>,
+}
+
+/// Data of a loaded world which the builder does not expose, so it can be written back unchanged.
+struct PreservedZone {
+    /// Of the world header.
+    world_unknown1: u32,
+    /// Without rooms.
+    zone: LOZone,
+    unknown1: u16,
+    id: u32,
+    metadata: LOZoneMetadata,
+    compatibility: u32,
+    extra_fields: Vec<LOField>,
+}
+
+/// What the game writes for new worlds.
+impl Default for PreservedZone {
+    fn default() -> Self {
+        Self {
+            world_unknown1: 0,
+            zone: LOZone::new(vec![]),
+            unknown1: 0,
+            id: 1,
+            metadata: LOZoneMetadata::default(),
+            compatibility: 0,
+            extra_fields: vec![],
+        }
+    }
 }
 
 impl World {
@@ -23,16 +85,20 @@ impl World {
         Self {
             name: "untitled".into(),
             description: "(no description provided)".into(),
-            author: Author::new("Player", &format!("{0}-{0}", INVALID_GUID)),
-            guid: INVALID_GUID.into(),
+            author: Author::new("Player", AuthorGuid::default()),
+            guid: WorldGuid::default(),
             revision: 0,
             rooms: vec![],
             room_width: 24,
             room_height: 16,
+            start_room: None,
             last_room_id: 0,
+            preserved: None,
+            journal: None,
         }
     }
 
+    /// Creates a room with a new id, which still has to be added to the world.
     pub fn new_room(&mut self, position: (i16, i16, i16)) -> Room {
         let position = (
             position.0 * self.room_width as i16,
@@ -40,19 +106,121 @@ impl World {
             position.2,
         );
         let mut room = Room::new_sized(position, self.room_width, self.room_height);
-        self.last_room_id += 1;
-        room.id = self.last_room_id;
+        room.id = self.next_room_id();
         room
     }
 
+    /// Also considers rooms which were pushed to `rooms` directly.
+    fn next_room_id(&mut self) -> u32 {
+        let max_id = self.rooms.iter().map(|room| room.id).max().unwrap_or(0);
+        self.last_room_id = self.last_room_id.max(max_id) + 1;
+        self.last_room_id
+    }
+
+    pub fn room(&self, id: u32) -> Option<&Room> {
+        self.rooms.iter().find(|room| room.id == id)
+    }
+
+    pub fn room_mut(&mut self, id: u32) -> Option<&mut Room> {
+        self.rooms.iter_mut().find(|room| room.id == id)
+    }
+
+    fn room_index(&self, id: u32) -> Result<usize, LOError> {
+        self.rooms
+            .iter()
+            .position(|room| room.id == id)
+            .ok_or(LOError::InvalidRoomId { id })
+    }
+
+    /// Adds the room, giving it a new id if it has none (0) or its id is already taken. Returns the id of the room.
+    pub fn add_room(&mut self, mut room: Room) -> u32 {
+        if room.id == 0 || self.room(room.id).is_some() {
+            room.id = self.next_room_id();
+        }
+        if self.journal.is_some() {
+            room.tilemap.record_edits(true);
+        }
+        let id = room.id;
+        self.rooms.push(room);
+        id
+    }
+
+    /// If the room was the start room, `start_room` is reset to `None`. Its edits are removed from the journal and the
+    /// returned room no longer records edits.
+    pub fn remove_room(&mut self, id: u32) -> Option<Room> {
+        let index = self.room_index(id).ok()?;
+        if self.start_room == Some(id) {
+            self.start_room = None;
+        }
+        self.commit();
+        if let Some(journal) = &mut self.journal {
+            journal.remove_room(id);
+        }
+        let mut room = self.rooms.remove(index);
+        room.tilemap.record_edits(false);
+        Some(room)
+    }
+
+    /// Adds a copy of the room at `position` (in tiles), returning the id of the copy.
+    pub fn duplicate_room(&mut self, id: u32, position: (i16, i16, i16)) -> Result<u32, LOError> {
+        let mut room = self.rooms[self.room_index(id)?].clone();
+        room.id = 0;
+        room.position = position;
+        Ok(self.add_room(room))
+    }
+
+    /// Moves the room to `index` in `rooms`, which is also the order of rooms in the written world.
+    pub fn reorder_room(&mut self, id: u32, index: usize) -> Result<(), LOError> {
+        if index >= self.rooms.len() {
+            return Err(LOError::InvalidRoomIndex { index });
+        }
+        let room = self.rooms.remove(self.room_index(id)?);
+        self.rooms.insert(index, room);
+        Ok(())
+    }
+
+    /// Renumbers rooms to 1, 2, 3... in their current order and updates `start_room` and the journal.
+    /// Returns the new id of each room by its old id, for remapping other references.
+    pub fn compact_room_ids(&mut self) -> HashMap<u32, u32> {
+        let mut new_ids = HashMap::new();
+        for (i, room) in self.rooms.iter_mut().enumerate() {
+            let new_id = i as u32 + 1;
+            new_ids.insert(room.id, new_id);
+            room.id = new_id;
+        }
+        self.start_room = self.start_room.and_then(|id| new_ids.get(&id).copied());
+        self.last_room_id = self.rooms.len() as u32;
+        if let Some(journal) = &mut self.journal {
+            journal.remap_rooms(&new_ids);
+        }
+        new_ids
+    }
+
+    /// Problems which do not stop the conversion into a [`LOWorld`], but which the game may not handle: multiple
+    /// start points, or none if `start_room` is `None` as well.
+    pub fn warnings(&self) -> Vec<LODiagnostic> {
+        let found = self
+            .rooms
+            .iter()
+            .filter_map(|room| room.tilemap.get_layer(Tilemap::LAYER4).ok())
+            .flat_map(|layer| layer.elements_row_major_iter())
+            .filter(|tile| matches!(tile, LOTile::StartPoint { .. }))
+            .count();
+        if found > 1 || (found == 0 && self.start_room.is_none()) {
+            vec![LODiagnostic::StartPointCount { found }]
+        } else {
+            vec![]
+        }
+    }
+
     pub fn with_metadata(mut self, name: &str, description: &str) -> Self {
         self.name = name.into();
         self.description = description.into();
         self
     }
 
-    pub fn with_identity(mut self, guid: &str, author: Author) -> Self {
-        self.guid = guid.into();
+    pub fn with_identity(mut self, guid: WorldGuid, author: Author) -> Self {
+        self.guid = guid;
         self.author = author;
         self
     }
@@ -60,18 +228,19 @@ impl World {
 
 pub struct Author {
     pub name: String,
-    pub guid: String,
+    pub guid: AuthorGuid,
 }
 
 impl Author {
-    pub fn new(name: &str, guid: &str) -> Self {
+    pub fn new(name: &str, guid: AuthorGuid) -> Self {
         Self {
             name: name.into(),
-            guid: guid.into()
+            guid,
         }
     }
 }
 
+#[derive(Clone)]
 pub struct Room {
     pub id: u32,
     pub name: String,
@@ -81,6 +250,20 @@ pub struct Room {
     pub music: LOMusic,
     pub revision: u32,
     pub tilemap: Tilemap,
+    preserved: Option<PreservedRoom>,
+}
+
+/// Data of a loaded room which the builder does not expose, so it can be written back unchanged.
+#[derive(Clone)]
+struct PreservedRoom {
+    entry: LORoom,
+    info: LORoomInfo,
+    unknown1: u16,
+    unknown2: u32,
+    metadata: LOTileMapMetadata,
+    /// Without tiles.
+    layers: Vec<LOLayer>,
+    extra_fields: Vec<LOField>,
 }
 
 impl Room {
@@ -98,6 +281,7 @@ impl Room {
             music: LOMusic::None,
             revision: 0,
             tilemap: Tilemap::new(width, height),
+            preserved: None,
         }
     }
 
@@ -106,8 +290,23 @@ impl Room {
         self.music = music;
         self
     }
+
+    fn room_info(&self) -> LORoomInfo {
+        match &self.preserved {
+            Some(preserved) => {
+                let mut info = preserved.info.clone();
+                info.id = self.id;
+                (info.x_position, info.y_position, info.z_position) = self.position;
+                info.width = self.width;
+                info.height = self.height;
+                info
+            }
+            None => LORoomInfo::new(self.id, self.position, self.width, self.height),
+        }
+    }
 }
 
+#[derive(Clone)]
 pub struct Tilemap {
     pub layers: (
         Array2D<LOTile>,
@@ -116,6 +315,7 @@ pub struct Tilemap {
         Array2D<LOTile>,
         Array2D<LOTile>,
     ),
+    recorded_edits: Option<Vec<TileEdit>>,
 }
 
 impl Tilemap {
@@ -141,6 +341,7 @@ impl Tilemap {
                 Array2D::filled_with(LOTile::None, height as usize, width as usize),
                 Array2D::filled_with(LOTile::None, height as usize, width as usize),
             ),
+            recorded_edits: None,
         }
     }
 
@@ -163,67 +364,73 @@ impl Tilemap {
         self.select_value(false)
     }
 
-    pub fn write_on_layer(&mut self, layer: u8, tile: &LOTile, selection: &TileSelection) {
-        let layer = self.get_layer_mut(layer);
+    pub fn write_on_layer(&mut self, layer: u8, tile: &LOTile, selection: &TileSelection) -> Result<(), LOError> {
+        self.get_layer(layer)?;
 
-        for ((row, col), value) in selection.bools.enumerate_row_major() {
-            if *value {
-                let _ = layer.set(row, col, tile.clone());
-            }
+        for (row, col) in selection.get_selection() {
+            let _ = self.set_tile_on_layer(layer, (col, row), tile.clone());
         }
+        Ok(())
     }
-    pub fn write_on_layer_if<F>(&mut self, layer: u8, tile: &LOTile, selection: &TileSelection, predicate: F)
+    pub fn write_on_layer_if<F>(&mut self, layer: u8, tile: &LOTile, selection: &TileSelection, predicate: F) -> Result<(), LOError>
         where F: Fn((usize, usize), &LOTile) -> bool {
+        self.get_layer(layer)?;
+        self.write_on_layer_if_cell(layer, tile, selection, |cell| {
+            cell.get(layer).is_some_and(|iter_tile| predicate(cell.position, iter_tile))
+        })
+    }
+    /// Like [`Self::write_on_layer_if`], with the tiles of all layers at each position.
+    pub fn write_on_layer_if_cell<F>(&mut self, layer: u8, tile: &LOTile, selection: &TileSelection, predicate: F) -> Result<(), LOError>
+        where F: Fn(&TileCell) -> bool {
         self.write_on_layer(layer, tile, &selection.clone().predicate_and(|x, y| {
-            let iter_tile = self.layers.1.get(y, x).unwrap();
-            predicate((x, y), &iter_tile)
-        }));
+            self.cell(x, y).is_some_and(|cell| predicate(&cell))
+        }))
     }
-    pub fn write(&mut self, tile: &LOTile, selection: &TileSelection) {
-        if tile.is_floor() {
+    pub fn write(&mut self, tile: &LOTile, selection: &TileSelection) -> Result<(), LOError> {
+        if tile.is_floor() || tile.is_puzzle_floor() {
             self.write_floor(tile, selection)
         } else if tile.is_wall() {
             self.write_wall(tile, selection)
-        } else if tile.is_obstacle() || tile.is_puzzle_obstacle() {
-            self.write_obstacle(tile, selection)
         } else if tile.is_trapdoor() {
+            // Trapdoors are puzzle obstacles as well, but need a fitting floor.
             self.write_trapdoor(tile, selection)
+        } else if tile.is_obstacle() || tile.is_puzzle_obstacle() {
+            self.write_obstacle(tile, selection)
         } else {
-            self.write_puzzle_element(tile, selection);
+            self.write_puzzle_element(tile, selection)
         }
     }
-    pub fn write_floor(&mut self, tile: &LOTile, selection: &TileSelection) {
-        assert!(tile.is_floor());
-        self.write_on_layer(Self::LAYER1, tile, selection);
+    pub fn write_floor(&mut self, tile: &LOTile, selection: &TileSelection) -> Result<(), LOError> {
+        Self::expect_tile(tile, tile.is_floor() || tile.is_puzzle_floor(), "a floor")?;
+        self.write_on_layer(Self::LAYER1, tile, selection)
     }
-    pub fn write_wall(&mut self, tile: &LOTile, selection: &TileSelection) {
-        assert!(tile.is_wall());
-        self.write_on_layer(Self::LAYER1, tile, selection);
+    pub fn write_wall(&mut self, tile: &LOTile, selection: &TileSelection) -> Result<(), LOError> {
+        Self::expect_tile(tile, tile.is_wall(), "a wall")?;
+        self.write_on_layer(Self::LAYER1, tile, selection)?;
         self.write_on_layer_if(Self::LAYER2, &LOTile::None, selection, |_, iter_tile| {
             matches!(iter_tile, LOTile::Pillar) || !iter_tile.is_puzzle_obstacle()
-        });
-        self.write_on_layer(Self::LAYER3, &LOTile::None, selection);
-        self.write_on_layer(Self::LAYER4, &LOTile::None, selection);
-        self.write_on_layer(Self::LAYER5, &LOTile::None, selection);
-    }
-    pub fn write_obstacle(&mut self, tile: &LOTile, selection: &TileSelection) {
-        assert!(tile.is_obstacle() || tile.is_puzzle_obstacle());
-        self.write_on_layer(Self::LAYER2, tile, selection);
-        self.write_on_layer(Self::LAYER3, &LOTile::None, selection);
-        self.write_on_layer(Self::LAYER4, &LOTile::None, selection);
-        self.write_on_layer(Self::LAYER5, &LOTile::None, selection);
-    }
-    pub fn write_trapdoor(&mut self, tile: &LOTile, selection: &TileSelection) {
-        assert!(tile.is_trapdoor());
-        let floors = tile.get_trapdoor_floors();
+        })?;
+        self.write_on_layer(Self::LAYER3, &LOTile::None, selection)?;
+        self.write_on_layer(Self::LAYER4, &LOTile::None, selection)?;
+        self.write_on_layer(Self::LAYER5, &LOTile::None, selection)
+    }
+    pub fn write_obstacle(&mut self, tile: &LOTile, selection: &TileSelection) -> Result<(), LOError> {
+        Self::expect_tile(tile, tile.is_obstacle() || tile.is_puzzle_obstacle(), "an obstacle")?;
+        self.write_on_layer(Self::LAYER2, tile, selection)?;
+        self.write_on_layer(Self::LAYER3, &LOTile::None, selection)?;
+        self.write_on_layer(Self::LAYER4, &LOTile::None, selection)?;
+        self.write_on_layer(Self::LAYER5, &LOTile::None, selection)
+    }
+    pub fn write_trapdoor(&mut self, tile: &LOTile, selection: &TileSelection) -> Result<(), LOError> {
+        let floors = tile.get_trapdoor_floors()?;
         let canonical_floor = &floors[0];
         // Write canonical floor if the current floor is not a valid trapdoor floor.
         self.write_on_layer_if(Self::LAYER1, canonical_floor, selection, |_, iter_tile| {
             !floors.iter().any(|floor_tile| iter_tile.same_type_as(floor_tile))
-        });
-        self.write_on_layer(Self::LAYER2, tile, selection);
+        })?;
+        self.write_on_layer(Self::LAYER2, tile, selection)
     }
-    pub fn write_puzzle_element(&mut self, tile: &LOTile, selection: &TileSelection) {
+    pub fn write_puzzle_element(&mut self, tile: &LOTile, selection: &TileSelection) -> Result<(), LOError> {
         let layer = if tile.is_puzzle_layer3() {
             Self::LAYER3
         } else if tile.is_puzzle_layer4() {
@@ -231,35 +438,50 @@ impl Tilemap {
         } else if tile.is_puzzle_layer5() || tile.is_monster() {
             Self::LAYER5
         } else {
-            panic!("Input {:?} is not a puzzle element", tile)
+            return Err(LOError::UnexpectedTile {
+                tile: tile.clone(),
+                expected: "a puzzle element",
+            });
         };
 
-        self.write_on_layer(layer, tile, selection);
+        self.write_on_layer(layer, tile, selection)?;
         if tile.is_crumbly_wall() {
-            self.write_on_layer(Self::LAYER4, &LOTile::None, selection);
-            self.write_on_layer(Self::LAYER5, &LOTile::None, selection);
+            self.write_on_layer(Self::LAYER4, &LOTile::None, selection)?;
+            self.write_on_layer(Self::LAYER5, &LOTile::None, selection)?;
         }
+        Ok(())
     }
 
-    pub fn get_layer(&self, layer: u8) -> &Array2D<LOTile> {
+    fn expect_tile(tile: &LOTile, valid: bool, expected: &'static str) -> Result<(), LOError> {
+        if valid {
+            Ok(())
+        } else {
+            Err(LOError::UnexpectedTile {
+                tile: tile.clone(),
+                expected,
+            })
+        }
+    }
+
+    pub fn get_layer(&self, layer: u8) -> Result<&Array2D<LOTile>, LOError> {
         match layer {
-            Self::LAYER1 => &self.layers.0,
-            Self::LAYER2 => &self.layers.1,
-            Self::LAYER3 => &self.layers.2,
-            Self::LAYER4 => &self.layers.3,
-            Self::LAYER5 => &self.layers.4,
-            _ => panic!("Layer out of bounds."),
+            Self::LAYER1 => Ok(&self.layers.0),
+            Self::LAYER2 => Ok(&self.layers.1),
+            Self::LAYER3 => Ok(&self.layers.2),
+            Self::LAYER4 => Ok(&self.layers.3),
+            Self::LAYER5 => Ok(&self.layers.4),
+            _ => Err(LOError::InvalidLayer { layer }),
         }
     }
 
-    pub fn get_layer_mut(&mut self, layer: u8) -> &mut Array2D<LOTile> {
+    pub fn get_layer_mut(&mut self, layer: u8) -> Result<&mut Array2D<LOTile>, LOError> {
         match layer {
-            Self::LAYER1 => &mut self.layers.0,
-            Self::LAYER2 => &mut self.layers.1,
-            Self::LAYER3 => &mut self.layers.2,
-            Self::LAYER4 => &mut self.layers.3,
-            Self::LAYER5 => &mut self.layers.4,
-            _ => panic!("Layer out of bounds."),
+            Self::LAYER1 => Ok(&mut self.layers.0),
+            Self::LAYER2 => Ok(&mut self.layers.1),
+            Self::LAYER3 => Ok(&mut self.layers.2),
+            Self::LAYER4 => Ok(&mut self.layers.3),
+            Self::LAYER5 => Ok(&mut self.layers.4),
+            _ => Err(LOError::InvalidLayer { layer }),
         }
     }
 
@@ -274,254 +496,235 @@ impl Tilemap {
     }
 
     fn into_layer(layer: Array2D<LOTile>) -> LOLayer {
-        LOLayer {
-            width: layer.num_columns() as u16,
-            height: layer.num_rows() as u16,
-            tiles: layer.as_row_major(),
-        }
-    }
-}
-
-#[derive(Clone)]
-pub struct TileSelection {
-    pub bools: Array2D<bool>,
-}
-
-impl From<(usize, usize)> for TileSelection {
-    // TODO: this is really inefficient, maybe we should have multiple backends?
-    fn from(value: (usize, usize)) -> Self {
-        let mut selection = Self::new(value.0, value.1);
-        let _ = selection.bools.set(value.1, value.0, true);
-        selection
+        LOLayer::new(layer.num_columns() as u16, layer.num_rows() as u16, layer.as_row_major())
     }
 }
 
-impl From<(usize, usize, usize, usize)> for TileSelection {
-    fn from(value: (usize, usize, usize, usize)) -> Self {
-        Self::new(value.0+value.2, value.1+value.3)
-            .set_rect(value.0, value.1, value.2, value.3, true)
-    }
-}
-
-impl TileSelection {
-    pub fn from_value(width: usize, height: usize, value: bool) -> Self {
-        Self {
-            bools: Array2D::filled_with(value, height, width)
-        }
-    }
-    /// Full selection.
-    pub fn all(width: usize, height: usize) -> Self {
-        Self::from_value(width, height, true)
-    }
-    /// Empty selection.
-    pub fn new(width: usize, height: usize) -> Self {
-        Self::from_value(width, height, false)
-    }
-
-    pub fn get_width(&self) -> usize {
-        self.bools.num_columns()
-    }
-    pub fn get_height(&self) -> usize {
-        self.bools.num_rows()
-    }
-    pub fn get_selection(&self) -> impl DoubleEndedIterator<Item = (usize, usize)> + Clone + use<'_> {
-        self.bools.enumerate_row_major()
-            .filter_map(|((row, col), mask)| {
-                if *mask {
-                    Some((row, col))
-                } else {
-                    None
+impl From<Room> for LOStemContent {
+    fn from(value: Room) -> Self {
+        let mut layers = value.tilemap.into_layers();
+        let (unknown1, unknown2, metadata, extra_fields) = match value.preserved {
+            Some(preserved) => {
+                for (layer, mut template) in layers.iter_mut().zip(preserved.layers) {
+                    template.width = layer.width;
+                    template.height = layer.height;
+                    template.tiles = std::mem::take(&mut layer.tiles);
+                    *layer = template;
                 }
-            })
-    }
-    /* TODO: not sure how I can borrow "self.bools" but not "self"
-    /// Every index in the area (does not matter if selected or unselected).
-    pub fn get_area(&self) -> impl DoubleEndedIterator<Item = (usize, usize)> + Clone + use<'_> {
-        self.bools.indices_row_major()
-    }
-    /// Every index in the area (does not matter if selected or unselected).
-    pub fn get_bools(&self) -> impl DoubleEndedIterator<Item = ((usize, usize), &bool)> + Clone + use<'_> {
-        self.bools.enumerate_row_major()
-    }
-    */
-
-    pub fn invert(mut self, selection: TileSelection) -> Self {
-        for (row, col) in selection.get_selection() {
-            if let Some(value_ref) = self.bools.get_mut(row, col) {
-                *value_ref = !*value_ref;
+                (preserved.unknown1, preserved.unknown2, preserved.metadata, preserved.extra_fields)
             }
-        }
-        self
-    }
-    pub fn invert_all(self) -> Self {
-        let size = (self.get_width(), self.get_height());
-        self.invert(
-            TileSelection::all(size.0, size.1)
-        )
-    }
-
-    pub fn set_all(mut self, value: bool) -> Self {
-        for (row, col) in self.bools.indices_row_major() {
-            let _ = self.bools.set(row, col, value);
-        }
-        self
-    }
-    pub fn add_all(self) -> Self {
-        self.set_all(true)
-    }
-    pub fn remove_all(self) -> Self {
-        self.set_all(false)
-    }
-
-    pub fn set(mut self, x: usize, y: usize, value: bool) -> Self {
-        let _ = self.bools.set(y, x, value);
-        self
-    }
-    pub fn add(self, x: usize, y: usize) -> Self {
-        self.set(x, y, true)
-    }
-    pub fn remove(self, x: usize, y: usize) -> Self {
-        self.set(x, y, false)
-    }
-
-    pub fn predicate<F>(mut self, predicate: F) -> Self
-        where F: Fn((usize, usize), bool) -> bool {
-        for (row, col) in self.bools.indices_row_major() {
-            // Convert ref to value copy to avoid double borrow.
-            let value = {
-                let reference = self.bools.get(row, col);
-                *reference.unwrap()
-            };
-            let result = predicate((col, row), value);
-            if result != value {
-                let _ = self.bools.set(row, col, result);
-            }
-        }
-        self
-    }
-
-    pub fn predicate_and<F>(self, predicate: F) -> Self
-        where F: Fn(usize, usize) -> bool {
-        self.predicate(|(x, y), value| {
-            if !value { return false; }
-            predicate(x, y)
-        })
-    }
-
-    pub fn predicate_or<F>(self, predicate: F) -> Self
-        where F: Fn(usize, usize) -> bool {
-        self.predicate(|(x, y), value| {
-            if value { return true;}
-            predicate(x, y)
-        })
-    }
-
-    pub fn set_rect(mut self, x: usize, y: usize, width: usize, height: usize, value: bool) -> Self {
-        for y in y..y+height {
-            for x in x..x+width {
-                let _ = self.bools.set(y, x, value);
-            }
-        }
-        self
-    }
-    pub fn add_rect(self, x: usize, y: usize, width: usize, height: usize) -> Self {
-        self.set_rect(x, y, width, height, true)
-    }
-    pub fn remove_rect(self, x: usize, y: usize, width: usize, height: usize) -> Self {
-        self.set_rect(x, y, width, height, false)
-    }
-}
+            None => (0, 0, LOTileMapMetadata::default(), vec![]),
+        };
 
-impl From<Room> for LOStemContent {
-    fn from(value: Room) -> Self {
         Self::TileMapEdit {
+            unknown1,
             id: value.id,
             name: value.name.into(),
             width: value.width,
             height: value.height,
-            layers: value.tilemap.into_layers(),
+            unknown2,
+            metadata,
+            layers,
             music: value.music,
             revision: value.revision,
+            extra_fields,
         }
     }
 }
 
 impl TryFrom<World> for LOWorld {
-    type Error = String;
+    type Error = LOError;
 
+    /// The first room with a start point is the start room if `start_room` is `None`, see [`World::warnings`] for
+    /// worlds with multiple or no start points.
     fn try_from(value: World) -> Result<Self, Self::Error> {
-        let room_count = value.rooms.len();
+        let mut ids = HashSet::new();
+        if let Some(room) = value.rooms.iter().find(|room| !ids.insert(room.id)) {
+            return Err(LOError::InvalidWorld {
+                diagnostic: LODiagnostic::DuplicateRoomId { room_id: room.id },
+            });
+        }
 
-        let room_info = value
+        let room_info = value.rooms.iter().map(Room::room_info).collect();
+        let zone_rooms = value
             .rooms
             .iter()
-            .enumerate()
-            .map(|(i, room)| LORoomInfo {
-                id: (i + 1) as u32,
-                width: room.width,
-                height: room.height,
-                x_position: room.position.0,
-                y_position: room.position.1,
-                z_position: room.position.2,
-            })
+            .map(|room| room.preserved.as_ref().map_or_else(LORoom::default, |preserved| preserved.entry.clone()))
             .collect();
 
         let mut starting_room: Option<u32> = None;
         let mut room_stems = vec![];
         for room in value.rooms {
-            for tile in room.tilemap.get_layer(Tilemap::LAYER4).elements_row_major_iter() {
-                if matches!(tile, LOTile::StartPoint {..}) {
-                    if starting_room.is_some() {
-                        println!("Multiple start points found in world!");
-                    } else {
-                        starting_room = Some(room.id);
-                    }
-                }
+            let layer = room.tilemap.get_layer(Tilemap::LAYER4)?;
+            if starting_room.is_none() && layer.elements_row_major_iter().any(|tile| matches!(tile, LOTile::StartPoint {..})) {
+                starting_room = Some(room.id);
             }
 
             let stem = LOStem::from_content(LOStemContent::from(room));
             room_stems.push(stem);
         }
 
-        let starting_room = match starting_room {
-            Some(starting_room) => starting_room,
-            None => {
-                println!("No start point found in world!");
-                1
-            },
-        };
+        // Without a start point, see `World::warnings`.
+        let starting_room = value.start_room.or(starting_room).unwrap_or(1);
 
-        let author_guids = parse_guid(&value.author.guid)?;
-        assert_eq!(author_guids.len(), 2);
+        let PreservedZone { world_unknown1, mut zone, unknown1, id, metadata, compatibility, extra_fields } =
+            value.preserved.unwrap_or_default();
+        zone.rooms = zone_rooms;
 
         let mut stems = vec![LOStem::from_content(LOStemContent::TileZoneMap {
+            unknown1,
+            id,
+            metadata,
+            listed_room_count: None,
             room_info,
             name: value.name.into(),
             description: value.description.into(),
             author: value.author.name.into(),
-            guid_world: parse_single_guid(&value.guid)?,
-            guid_author1: author_guids[0],
-            guid_author2: author_guids[1],
+            guid_world: value.guid,
+            guid_author: value.author.guid,
             world_revision: value.revision,
             start_room: starting_room,
-            compatibility: 0,
+            compatibility,
+            extra_fields,
         })];
 
         stems.append(&mut room_stems);
 
-        Ok(Self::new(
-            LOZone {
-                stem_offset: 0,
-                stem_length: 0,
-                rooms: vec![
-                    LORoom {
-                        stem_offset: 0,
-                        stem_length: 0,
-                    };
-                    room_count
-                ],
+        let mut world = Self::new(zone, stems);
+        world.unknown1 = world_unknown1;
+        Ok(world)
+    }
+}
+
+impl TryFrom<LOWorld> for World {
+    type Error = LOError;
+
+    /// Keeps everything the builder does not expose, so converting back results in the same world.
+    /// Room sizes are taken from the zone map. Stems which are no room of the zone map can not be kept, so they
+    /// result in an error.
+    fn try_from(value: LOWorld) -> Result<Self, Self::Error> {
+        let invalid = |diagnostic| LOError::InvalidWorld { diagnostic };
+        let LOWorld { unknown1: world_unknown1, mut zone, stems, room_count, .. } = value;
+        let mut stems = stems.into_iter();
+
+        let Some(LOStemContent::TileZoneMap {
+            unknown1,
+            id,
+            name,
+            metadata,
+            room_info,
+            start_room,
+            description,
+            author,
+            guid_world,
+            guid_author,
+            world_revision,
+            listed_room_count,
+            compatibility,
+            extra_fields,
+        }) = stems.next().map(|stem| stem.content)
+        else {
+            return Err(invalid(LODiagnostic::MissingZoneMap));
+        };
+
+        if room_count as usize != zone.rooms.len() || zone.rooms.len() != room_info.len() {
+            return Err(invalid(LODiagnostic::RoomCountMismatch {
+                world: room_count,
+                zone: zone.rooms.len(),
+                zone_map: room_info.len(),
+            }));
+        }
+        if let Some(found) = listed_room_count.filter(|&found| found as usize != room_info.len()) {
+            return Err(invalid(LODiagnostic::ZoneMapRoomCount { found, expected: room_info.len() }));
+        }
+
+        // Paired with their index in `LOWorld::stems`.
+        let mut room_stems: Vec<_> = stems.map(|stem| stem.content).zip(1..).collect();
+        let mut rooms = vec![];
+        for (entry, info) in std::mem::take(&mut zone.rooms).into_iter().zip(room_info) {
+            let index = room_stems
+                .iter()
+                .position(|(content, _)| matches!(content, LOStemContent::TileMapEdit { id, .. } if *id == info.id))
+                .ok_or_else(|| invalid(LODiagnostic::MissingRoomStem { room_id: info.id }))?;
+            let (
+                LOStemContent::TileMapEdit {
+                    unknown1, id, name, unknown2, metadata, mut layers, music, revision, extra_fields, ..
+                },
+                _,
+            ) = room_stems.remove(index)
+            else {
+                unreachable!();
+            };
+
+            let mut arrays = vec![];
+            for (i, layer) in layers.iter_mut().enumerate() {
+                let tiles = std::mem::take(&mut layer.tiles);
+                let array = Array2D::from_row_major(&tiles, layer.height as usize, layer.width as usize).map_err(|_| {
+                    invalid(LODiagnostic::TileCount {
+                        room_id: id,
+                        layer: i as u8,
+                        found: tiles.len(),
+                        expected: layer.width as usize * layer.height as usize,
+                    })
+                })?;
+                arrays.push(array);
+            }
+            let [layer1, layer2, layer3, layer4, layer5] = <[_; 5]>::try_from(arrays)
+                .map_err(|arrays| invalid(LODiagnostic::LayerCount { room_id: id, found: arrays.len() }))?;
+            let tilemap = Tilemap {
+                layers: (layer1, layer2, layer3, layer4, layer5),
+                recorded_edits: None,
+            };
+
+            rooms.push(Room {
+                id,
+                name: name.to_string(),
+                position: (info.x_position, info.y_position, info.z_position),
+                width: info.width,
+                height: info.height,
+                music,
+                revision,
+                tilemap,
+                preserved: Some(PreservedRoom {
+                    entry,
+                    info,
+                    unknown1,
+                    unknown2,
+                    metadata,
+                    layers,
+                    extra_fields,
+                }),
+            });
+        }
+
+        if let Some((_, stem_index)) = room_stems.first() {
+            return Err(invalid(LODiagnostic::UnreferencedStem { stem_index: *stem_index }));
+        }
+
+        Ok(Self {
+            name: name.to_string(),
+            description: description.to_string(),
+            author: Author {
+                name: author.to_string(),
+                guid: guid_author,
             },
-            stems,
-        ))
+            guid: guid_world,
+            revision: world_revision,
+            last_room_id: rooms.iter().map(|room| room.id).max().unwrap_or(0),
+            rooms,
+            room_width: 24,
+            room_height: 16,
+            start_room: Some(start_room),
+            preserved: Some(PreservedZone {
+                world_unknown1,
+                zone,
+                unknown1,
+                id,
+                metadata,
+                compatibility,
+                extra_fields,
+            }),
+            journal: None,
+        })
     }
 }
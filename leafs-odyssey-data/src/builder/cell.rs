@@ -0,0 +1,104 @@
+use crate::data::LOTile;
+
+use super::Tilemap;
+
+/// The tiles of all five layers at one position of a tilemap.
+#[derive(Clone, Copy, Debug)]
+pub struct TileCell<'a> {
+    /// `(x, y)` in the tilemap.
+    pub position: (usize, usize),
+    tiles: [&'a LOTile; 5],
+}
+
+impl<'a> TileCell<'a> {
+    /// `None` for invalid layers.
+    pub fn get(&self, layer: u8) -> Option<&'a LOTile> {
+        self.tiles.get(layer as usize).copied()
+    }
+
+    /// Tiles from `LAYER1` to `LAYER5`.
+    pub fn tiles(&self) -> [&'a LOTile; 5] {
+        self.tiles
+    }
+
+    /// Layers which are not `LOTile::None`, with their tile.
+    pub fn occupied(&self) -> impl Iterator<Item = (u8, &'a LOTile)> + use<'a> {
+        Tilemap::LAYER_INDICES
+            .zip(self.tiles)
+            .filter(|(_, tile)| !matches!(tile, LOTile::None))
+    }
+
+    pub fn is_empty_on(&self, layer: u8) -> bool {
+        self.get(layer).is_some_and(|tile| matches!(tile, LOTile::None))
+    }
+
+    fn floor(&self) -> &'a LOTile {
+        self.tiles[Tilemap::LAYER1 as usize]
+    }
+    fn obstacle(&self) -> &'a LOTile {
+        self.tiles[Tilemap::LAYER2 as usize]
+    }
+}
+
+impl Tilemap {
+    /// `None` outside of the tilemap.
+    pub fn cell(&self, x: usize, y: usize) -> Option<TileCell<'_>> {
+        let tiles = Tilemap::LAYER_INDICES.map(|layer| self.get_layer(layer).ok()?.get(y, x)).collect::<Option<Vec<_>>>()?;
+        Some(TileCell {
+            position: (x, y),
+            tiles: tiles.try_into().unwrap(),
+        })
+    }
+
+    /// Layer which [`Self::write`] puts the tile on, `None` if it can not be written.
+    pub fn layer_of(tile: &LOTile) -> Option<u8> {
+        if tile.is_floor() || tile.is_puzzle_floor() || tile.is_wall() {
+            Some(Self::LAYER1)
+        } else if tile.is_obstacle() || tile.is_puzzle_obstacle() {
+            Some(Self::LAYER2)
+        } else if tile.is_puzzle_layer3() {
+            Some(Self::LAYER3)
+        } else if tile.is_puzzle_layer4() {
+            Some(Self::LAYER4)
+        } else if tile.is_puzzle_layer5() || tile.is_monster() {
+            Some(Self::LAYER5)
+        } else {
+            None
+        }
+    }
+
+    /// Whether the tile can be written at the position without [`Self::write`] removing or replacing tiles on other
+    /// layers, following the editor's rules for which tiles can share a position:
+    /// - Walls only share their position with puzzle elements of `LAYER2`, like ladders.
+    /// - Trapdoors need one of their floors, see `LOTile::get_trapdoor_floors`.
+    /// - `LAYER2` tiles other than trapdoors are written without anything on top, and regular obstacles can not get
+    ///   anything placed on top of them either. Crumbly walls have nothing on `LAYER4` and `LAYER5`.
+    ///
+    /// `false` outside of the tilemap.
+    pub fn can_place(&self, tile: &LOTile, x: usize, y: usize) -> bool {
+        let (Some(cell), Some(layer)) = (self.cell(x, y), Self::layer_of(tile)) else {
+            return false;
+        };
+        let empty_above = |layer: u8| (layer + 1..=Self::LAYER5).all(|layer| cell.is_empty_on(layer));
+        let on_ground = || !cell.floor().is_wall() && !cell.obstacle().is_obstacle();
+
+        match layer {
+            Self::LAYER1 if tile.is_wall() => {
+                empty_above(Self::LAYER2) && (cell.is_empty_on(Self::LAYER2) || cell.obstacle().is_puzzle_obstacle())
+            }
+            Self::LAYER1 => match cell.obstacle().get_trapdoor_floors() {
+                Ok(floors) => floors.iter().any(|floor| floor.same_type_as(tile)),
+                Err(_) => true,
+            },
+            Self::LAYER2 => {
+                let floor_fits = match tile.get_trapdoor_floors() {
+                    Ok(floors) => floors.iter().any(|floor| floor.same_type_as(cell.floor())),
+                    Err(_) => true,
+                };
+                floor_fits && (tile.is_trapdoor() || empty_above(Self::LAYER2))
+            }
+            Self::LAYER3 => on_ground() && (!tile.is_crumbly_wall() || empty_above(Self::LAYER3)),
+            _ => on_ground() && !cell.get(Self::LAYER3).is_some_and(LOTile::is_crumbly_wall),
+        }
+    }
+}
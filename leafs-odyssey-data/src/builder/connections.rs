@@ -0,0 +1,265 @@
+use std::collections::HashSet;
+
+use crate::{
+    data::{LOConnection, LOStackElement, LOStackTile, LOTile},
+    error::LOError,
+};
+
+use super::Tilemap;
+
+/// Pressure plates and sacrifice altars are on `LAYER2`, toggle switches (also stacked ones) on `LAYER5`.
+const TRIGGER_LAYERS: [u8; 2] = [Tilemap::LAYER2, Tilemap::LAYER5];
+/// Toggle floors are on `LAYER2`, toggle doors on `LAYER3`.
+const TOGGLEABLE_LAYERS: [u8; 2] = [Tilemap::LAYER2, Tilemap::LAYER3];
+
+/// A trigger tile wired to the tile at `target`. Positions are `(x, y)` in the tilemap.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct TileConnection {
+    /// Layer of the trigger.
+    pub layer: u8,
+    pub trigger: (usize, usize),
+    pub target: (usize, usize),
+}
+
+/// Fails for positions which do not fit into a connection.
+pub(super) fn to_connection(position: (usize, usize)) -> Result<LOConnection, LOError> {
+    match (u16::try_from(position.0), u16::try_from(position.1)) {
+        (Ok(x), Ok(y)) => Ok(LOConnection::new(x, y)),
+        _ => Err(LOError::InvalidPosition { x: position.0, y: position.1 }),
+    }
+}
+
+fn to_position(connection: &LOConnection) -> (usize, usize) {
+    (connection.x_position as usize, connection.y_position as usize)
+}
+
+/// Whether the tile is a trigger or a stack with a toggle switch.
+fn has_trigger(tile: &LOTile) -> bool {
+    match tile {
+        LOTile::Stack { tiles, .. } => tiles.iter().any(|element| element.tile == LOStackTile::ToggleSwitch),
+        tile => tile.is_trigger(),
+    }
+}
+
+/// Connections which [`Tilemap::connect`] adds to, which are those of the first toggle switch for stacks.
+fn wired_connections(tile: &mut LOTile) -> Option<&mut Vec<LOConnection>> {
+    match tile {
+        LOTile::Stack { tiles, .. } => tiles
+            .iter_mut()
+            .find(|element| element.tile == LOStackTile::ToggleSwitch)
+            .map(LOStackElement::connections_mut),
+        tile => tile.trigger_connections_mut(),
+    }
+}
+
+impl Tilemap {
+    fn tile_at(&self, layer: u8, position: (usize, usize)) -> Option<&LOTile> {
+        self.get_layer(layer).ok()?.get(position.1, position.0)
+    }
+
+    fn check_position(&self, position: (usize, usize)) -> Result<(), LOError> {
+        if position.0 < self.get_width() as usize && position.1 < self.get_height() as usize {
+            Ok(())
+        } else {
+            Err(LOError::InvalidPosition { x: position.0, y: position.1 })
+        }
+    }
+
+    /// Layer of the trigger tile at the position. If there are triggers on both layers, `LAYER2` is used.
+    fn trigger_layer(&self, position: (usize, usize)) -> Result<u8, LOError> {
+        self.check_position(position)?;
+        let layer = TRIGGER_LAYERS
+            .into_iter()
+            .find(|layer| self.tile_at(*layer, position).is_some_and(has_trigger));
+        match layer {
+            Some(layer) => Ok(layer),
+            None => Err(LOError::UnexpectedTile {
+                tile: TRIGGER_LAYERS
+                    .into_iter()
+                    .filter_map(|layer| self.tile_at(layer, position))
+                    .find(|tile| !matches!(tile, LOTile::None))
+                    .cloned()
+                    .unwrap_or(LOTile::None),
+                expected: "a pressure plate, sacrifice altar or toggle switch",
+            }),
+        }
+    }
+
+    /// Changes the connections of the trigger tile at the position, returning the result of `change`.
+    fn change_trigger<T, F>(&mut self, position: (usize, usize), change: F) -> Result<T, LOError>
+    where
+        F: FnOnce(&mut Vec<LOConnection>) -> T,
+    {
+        let layer = self.trigger_layer(position)?;
+        let mut tile = self.tile_at(layer, position).unwrap().clone();
+        let result = change(wired_connections(&mut tile).unwrap());
+        self.set_tile_on_layer(layer, position, tile)?;
+        Ok(result)
+    }
+
+    /// Whether a toggle floor or toggle door is at the position, on the layer [`Self::write`] puts it on.
+    pub fn is_toggleable_at(&self, position: (usize, usize)) -> bool {
+        TOGGLEABLE_LAYERS.into_iter().any(|layer| {
+            self.tile_at(layer, position)
+                .is_some_and(|tile| tile.is_toggleable() && Self::layer_of(tile) == Some(layer))
+        })
+    }
+
+    /// Wires the pressure plate, sacrifice altar or toggle switch at `trigger` to the toggle floor or door at
+    /// `target`. Connecting the same tiles twice has no effect. Of a stack, the first toggle switch is wired, others
+    /// can be wired with [`super::StackBuilder::connect`].
+    pub fn connect(&mut self, trigger: (usize, usize), target: (usize, usize)) -> Result<(), LOError> {
+        self.check_position(target)?;
+        if !self.is_toggleable_at(target) {
+            return Err(LOError::UnexpectedTile {
+                tile: TOGGLEABLE_LAYERS
+                    .into_iter()
+                    .filter_map(|layer| self.tile_at(layer, target))
+                    .find(|tile| !matches!(tile, LOTile::None))
+                    .cloned()
+                    .unwrap_or(LOTile::None),
+                expected: "a toggle floor or door",
+            });
+        }
+
+        let connection = to_connection(target)?;
+        self.change_trigger(trigger, |connections| {
+            if !connections.contains(&connection) {
+                connections.push(connection);
+            }
+        })
+    }
+
+    /// Returns whether the tiles were connected. Of a stack, every toggle switch is disconnected.
+    pub fn disconnect(&mut self, trigger: (usize, usize), target: (usize, usize)) -> Result<bool, LOError> {
+        let layer = self.trigger_layer(trigger)?;
+        let connection = to_connection(target)?;
+        let removed = self.retain_connections_at(layer, trigger, |other| *other != connection);
+        Ok(removed > 0)
+    }
+
+    /// Removes connections of the tile for which `keep` returns false, returning how many were removed.
+    fn retain_connections_at<F>(&mut self, layer: u8, position: (usize, usize), keep: F) -> usize
+    where
+        F: Fn(&LOConnection) -> bool,
+    {
+        let Some(mut tile) = self.tile_at(layer, position).cloned() else {
+            return 0;
+        };
+        let count = tile.connections().len();
+        tile.retain_connections(keep);
+        let removed = count - tile.connections().len();
+        if removed > 0 {
+            self.set_tile_on_layer(layer, position, tile).unwrap();
+        }
+        removed
+    }
+
+    /// Every connection of the tilemap, including those of stacked toggle switches.
+    pub fn connections(&self) -> Vec<TileConnection> {
+        let mut result = vec![];
+        for layer in TRIGGER_LAYERS {
+            let Ok(tiles) = self.get_layer(layer) else {
+                continue;
+            };
+            for ((y, x), tile) in tiles.enumerate_row_major() {
+                for connection in tile.connections() {
+                    result.push(TileConnection {
+                        layer,
+                        trigger: (x, y),
+                        target: to_position(connection),
+                    });
+                }
+            }
+        }
+        result
+    }
+
+    /// Positions the trigger at the position is wired to.
+    pub fn targets_of(&self, trigger: (usize, usize)) -> Vec<(usize, usize)> {
+        self.connections()
+            .into_iter()
+            .filter(|connection| connection.trigger == trigger)
+            .map(|connection| connection.target)
+            .collect()
+    }
+
+    /// Triggers which are wired to the position.
+    pub fn triggers_of(&self, target: (usize, usize)) -> Vec<TileConnection> {
+        self.connections()
+            .into_iter()
+            .filter(|connection| connection.target == target)
+            .collect()
+    }
+
+    /// Connections whose target is not a toggle floor or door.
+    pub fn invalid_connections(&self) -> Vec<TileConnection> {
+        self.connections()
+            .into_iter()
+            .filter(|connection| !self.is_toggleable_at(connection.target))
+            .collect()
+    }
+
+    /// Removes the connections of [`Self::invalid_connections`], returning how many were removed.
+    pub fn remove_invalid_connections(&mut self) -> usize {
+        let mut triggers: Vec<_> = self
+            .invalid_connections()
+            .into_iter()
+            .map(|connection| (connection.layer, connection.trigger))
+            .collect();
+        triggers.dedup();
+
+        // Checked up front, as the tilemap can not be borrowed while changing it.
+        let valid: HashSet<_> = self
+            .connections()
+            .into_iter()
+            .map(|connection| connection.target)
+            .filter(|target| self.is_toggleable_at(*target))
+            .collect();
+        triggers
+            .into_iter()
+            .map(|(layer, position)| {
+                self.retain_connections_at(layer, position, |connection| valid.contains(&to_position(connection)))
+            })
+            .sum()
+    }
+
+    /// Moves the tile from one position to another, leaving `LOTile::None` behind. If a toggle floor or door is
+    /// moved, connections to it are moved along.
+    pub fn move_tile(&mut self, layer: u8, from: (usize, usize), to: (usize, usize)) -> Result<(), LOError> {
+        self.check_position(from)?;
+        self.check_position(to)?;
+        if from == to {
+            return Ok(());
+        }
+
+        let tile = self.get_layer(layer)?[(from.1, from.0)].clone();
+        let toggleable = tile.is_toggleable() && Self::layer_of(&tile) == Some(layer);
+        self.set_tile_on_layer(layer, from, LOTile::None)?;
+        self.set_tile_on_layer(layer, to, tile)?;
+
+        // Connections only store positions, so keep them if another toggleable tile stays behind.
+        if toggleable && !self.is_toggleable_at(from) {
+            let (from, to) = (to_connection(from)?, to_connection(to)?);
+            let mut moved = vec![];
+            for layer in TRIGGER_LAYERS {
+                for ((row, column), tile) in self.get_layer(layer)?.enumerate_row_major() {
+                    if !tile.connections().contains(&&from) {
+                        continue;
+                    }
+                    let mut tile = tile.clone();
+                    for connection in tile.connections_mut() {
+                        if *connection == from {
+                            *connection = to;
+                        }
+                    }
+                    moved.push((layer, (column, row), tile));
+                }
+            }
+            for (layer, position, tile) in moved {
+                self.set_tile_on_layer(layer, position, tile)?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,78 @@
+use crate::{data::LOTile, error::LOError};
+
+use super::{TileSelection, Tilemap};
+
+/// Which neighbors of a tile count as connected to it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Connectivity {
+    /// Left, right, up and down.
+    Four,
+    /// Also the diagonals.
+    Eight,
+}
+
+impl Connectivity {
+    /// `(x, y)` offsets of the neighbors.
+    pub fn offsets(&self) -> &'static [(isize, isize)] {
+        match self {
+            Self::Four => &[(0, -1), (1, 0), (0, 1), (-1, 0)],
+            Self::Eight => &[(0, -1), (1, -1), (1, 0), (1, 1), (0, 1), (-1, 1), (-1, 0), (-1, -1)],
+        }
+    }
+}
+
+impl Tilemap {
+    /// Every tile on the layer matching the predicate, e.g. `LOTile::is_floor`.
+    pub fn select_where<F>(&self, layer: u8, predicate: F) -> Result<TileSelection, LOError>
+    where
+        F: Fn(&LOTile) -> bool,
+    {
+        let tiles = self.get_layer(layer)?;
+        Ok(self.select().predicate_or(|x, y| predicate(&tiles[(y, x)])))
+    }
+
+    /// Every tile on the layer of the same type as `tile`, ignoring values like directions or connections.
+    pub fn select_same_type(&self, layer: u8, tile: &LOTile) -> Result<TileSelection, LOError> {
+        self.select_where(layer, |other| other.same_type_as(tile))
+    }
+
+    /// Tiles matching the predicate which are connected to `seed` through other matching tiles. Empty if the tile at
+    /// `seed` does not match.
+    pub fn select_connected_where<F>(&self, layer: u8, seed: (usize, usize), connectivity: Connectivity, predicate: F) -> Result<TileSelection, LOError>
+    where
+        F: Fn(&LOTile) -> bool,
+    {
+        let tiles = self.get_layer(layer)?;
+        let Some(tile) = tiles.get(seed.1, seed.0) else {
+            return Err(LOError::InvalidPosition { x: seed.0, y: seed.1 });
+        };
+
+        let mut selection = self.select();
+        if !predicate(tile) {
+            return Ok(selection);
+        }
+        selection = selection.add(seed.0, seed.1);
+        let mut pending = vec![seed];
+        while let Some((x, y)) = pending.pop() {
+            for (offset_x, offset_y) in connectivity.offsets() {
+                let (Some(x), Some(y)) = (x.checked_add_signed(*offset_x), y.checked_add_signed(*offset_y)) else {
+                    continue;
+                };
+                if !selection.contains(x, y) && tiles.get(y, x).is_some_and(&predicate) {
+                    selection = selection.add(x, y);
+                    pending.push((x, y));
+                }
+            }
+        }
+        Ok(selection)
+    }
+
+    /// Tiles of the same type as the one at `seed` which are connected to it, like the editor's magic wand.
+    pub fn select_connected(&self, layer: u8, seed: (usize, usize), connectivity: Connectivity) -> Result<TileSelection, LOError> {
+        let tile = self
+            .get_layer(layer)?
+            .get(seed.1, seed.0)
+            .ok_or(LOError::InvalidPosition { x: seed.0, y: seed.1 })?;
+        self.select_connected_where(layer, seed, connectivity, |other| other.same_type_as(tile))
+    }
+}
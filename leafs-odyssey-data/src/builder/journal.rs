@@ -0,0 +1,263 @@
+use std::collections::HashMap;
+
+use crate::{data::LOTile, error::LOError};
+
+use super::{Tilemap, World};
+
+/// A tile which was replaced on a tilemap.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TileEdit {
+    pub layer: u8,
+    pub position: (usize, usize),
+    pub old: LOTile,
+    pub new: LOTile,
+}
+
+/// Edits which are undone and redone together, with the id of the room they were made in.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Transaction {
+    pub edits: Vec<(u32, TileEdit)>,
+}
+
+/// Transactions recorded by [`World::enable_journal`]. Only tile changes are recorded, not rooms being added,
+/// removed or transformed. Edits of removed rooms are dropped and compacted room ids are updated.
+#[derive(Clone, Debug, Default)]
+pub struct Journal {
+    transactions: Vec<Transaction>,
+    /// Number of transactions at the end of `transactions` which were undone.
+    undone: usize,
+}
+
+impl Journal {
+    /// Transactions which were not undone, oldest first.
+    pub fn transactions(&self) -> &[Transaction] {
+        &self.transactions[..self.transactions.len() - self.undone]
+    }
+
+    pub fn can_undo(&self) -> bool {
+        self.undone < self.transactions.len()
+    }
+    pub fn can_redo(&self) -> bool {
+        self.undone > 0
+    }
+
+    /// Drops the edits of a removed room, and transactions left without edits.
+    pub(super) fn remove_room(&mut self, id: u32) {
+        let done = self.transactions.len() - self.undone;
+        let (mut index, mut undone) = (0, 0);
+        self.transactions.retain_mut(|transaction| {
+            transaction.edits.retain(|(room, _)| *room != id);
+            let keep = !transaction.edits.is_empty();
+            if keep && index >= done {
+                undone += 1;
+            }
+            index += 1;
+            keep
+        });
+        self.undone = undone;
+    }
+
+    /// Replaces room ids, like those returned by [`World::compact_room_ids`].
+    pub(super) fn remap_rooms(&mut self, new_ids: &HashMap<u32, u32>) {
+        for (id, _) in self.transactions.iter_mut().flat_map(|transaction| &mut transaction.edits) {
+            if let Some(new_id) = new_ids.get(id) {
+                *id = *new_id;
+            }
+        }
+    }
+
+    /// Discards the undone transactions.
+    fn push(&mut self, transaction: Transaction) {
+        self.transactions.truncate(self.transactions.len() - self.undone);
+        self.undone = 0;
+        self.transactions.push(transaction);
+    }
+}
+
+impl Tilemap {
+    /// Replaces a single tile, which is recorded if [`Self::record_edits`] is enabled.
+    /// Replacing a tile with an equal one is not recorded.
+    pub fn set_tile_on_layer(&mut self, layer: u8, position: (usize, usize), tile: LOTile) -> Result<(), LOError> {
+        let current = self
+            .get_layer(layer)?
+            .get(position.1, position.0)
+            .ok_or(LOError::InvalidPosition { x: position.0, y: position.1 })?;
+        if *current == tile {
+            return Ok(());
+        }
+
+        let edit = self.recorded_edits.is_some().then(|| TileEdit {
+            layer,
+            position,
+            old: current.clone(),
+            new: tile.clone(),
+        });
+        self.get_layer_mut(layer)?[(position.1, position.0)] = tile;
+        if let (Some(edits), Some(edit)) = (&mut self.recorded_edits, edit) {
+            edits.push(edit);
+        }
+        Ok(())
+    }
+
+    /// Starts or stops recording edits made through the methods of the tilemap. Changes to `layers` are not seen.
+    /// Stopping discards edits which were not taken yet.
+    pub fn record_edits(&mut self, enabled: bool) {
+        self.recorded_edits = enabled.then(|| self.recorded_edits.take().unwrap_or_default());
+    }
+    pub fn is_recording_edits(&self) -> bool {
+        self.recorded_edits.is_some()
+    }
+
+    /// Edits recorded since recording started or this was last called, oldest first.
+    pub fn take_edits(&mut self) -> Vec<TileEdit> {
+        self.recorded_edits.as_mut().map(std::mem::take).unwrap_or_default()
+    }
+
+    /// Sets the new tiles of the edits, in order.
+    pub fn redo_edits<'a, I>(&mut self, edits: I) -> Result<(), LOError>
+    where
+        I: IntoIterator<Item = &'a TileEdit>,
+    {
+        for edit in edits {
+            self.set_tile_on_layer(edit.layer, edit.position, edit.new.clone())?;
+        }
+        Ok(())
+    }
+
+    /// Sets the old tiles of the edits, in reverse order.
+    pub fn undo_edits<'a, I>(&mut self, edits: I) -> Result<(), LOError>
+    where
+        I: IntoIterator<Item = &'a TileEdit>,
+        I::IntoIter: DoubleEndedIterator,
+    {
+        for edit in edits.into_iter().rev() {
+            self.set_tile_on_layer(edit.layer, edit.position, edit.old.clone())?;
+        }
+        Ok(())
+    }
+}
+
+impl World {
+    /// Starts recording tile changes of all rooms, including those added later. Edits are grouped into a transaction
+    /// on every [`Self::commit`].
+    pub fn enable_journal(&mut self) {
+        self.journal.get_or_insert_with(Journal::default);
+        for room in &mut self.rooms {
+            room.tilemap.record_edits(true);
+        }
+    }
+
+    /// Stops recording, returning the journal with pending edits committed.
+    pub fn disable_journal(&mut self) -> Option<Journal> {
+        self.commit();
+        for room in &mut self.rooms {
+            room.tilemap.record_edits(false);
+        }
+        self.journal.take()
+    }
+
+    pub fn journal(&self) -> Option<&Journal> {
+        self.journal.as_ref()
+    }
+
+    /// Groups the edits since the last commit into a transaction. Does nothing without a journal or edits.
+    pub fn commit(&mut self) {
+        let Some(journal) = &mut self.journal else {
+            return;
+        };
+        let edits: Vec<_> = self
+            .rooms
+            .iter_mut()
+            .flat_map(|room| {
+                let id = room.id;
+                room.tilemap.take_edits().into_iter().map(move |edit| (id, edit))
+            })
+            .collect();
+        if !edits.is_empty() {
+            journal.push(Transaction { edits });
+        }
+    }
+
+    /// Runs `edit` and commits its changes as one transaction.
+    pub fn transaction<T, F>(&mut self, edit: F) -> T
+    where
+        F: FnOnce(&mut Self) -> T,
+    {
+        self.commit();
+        let result = edit(self);
+        self.commit();
+        result
+    }
+
+    /// Drops edits which were recorded while applying a transaction.
+    fn discard_edits(&mut self) {
+        for room in &mut self.rooms {
+            room.tilemap.take_edits();
+        }
+    }
+
+    /// Checks that every edit of the transactions has a room and position to be applied to, so an error does not leave
+    /// a transaction half applied.
+    fn check_edits<'a, I>(&self, transactions: I) -> Result<(), LOError>
+    where
+        I: IntoIterator<Item = &'a Transaction>,
+    {
+        for (id, edit) in transactions.into_iter().flat_map(|transaction| &transaction.edits) {
+            let room = self.room(*id).ok_or(LOError::InvalidRoomId { id: *id })?;
+            let (x, y) = edit.position;
+            room.tilemap.get_layer(edit.layer)?.get(y, x).ok_or(LOError::InvalidPosition { x, y })?;
+        }
+        Ok(())
+    }
+
+    /// Commits pending edits and reverts the last transaction. Returns whether there was one to undo.
+    /// Nothing is reverted if a room of the transaction was removed.
+    pub fn undo(&mut self) -> Result<bool, LOError> {
+        self.commit();
+        let Some(transaction) = self.journal.as_ref().and_then(|journal| journal.transactions().last().cloned()) else {
+            return Ok(false);
+        };
+        self.check_edits([&transaction])?;
+        for (id, edit) in transaction.edits.iter().rev() {
+            let room = self.room_mut(*id).ok_or(LOError::InvalidRoomId { id: *id })?;
+            room.tilemap.undo_edits([edit])?;
+        }
+        self.discard_edits();
+        self.journal.as_mut().unwrap().undone += 1;
+        Ok(true)
+    }
+
+    /// Applies the last undone transaction again. Returns whether there was one to redo.
+    /// New edits since the undo discard the undone transactions.
+    pub fn redo(&mut self) -> Result<bool, LOError> {
+        self.commit();
+        let next = |journal: &Journal| journal.transactions.get(journal.transactions.len() - journal.undone).cloned();
+        let Some(transaction) = self.journal.as_ref().filter(|journal| journal.can_redo()).and_then(next) else {
+            return Ok(false);
+        };
+        self.check_edits([&transaction])?;
+        for (id, edit) in &transaction.edits {
+            let room = self.room_mut(*id).ok_or(LOError::InvalidRoomId { id: *id })?;
+            room.tilemap.redo_edits([edit])?;
+        }
+        self.discard_edits();
+        self.journal.as_mut().unwrap().undone -= 1;
+        Ok(true)
+    }
+
+    /// Applies the transactions of a journal, which may come from another world with the same room ids.
+    /// If this world has a journal, each transaction is recorded as well. Nothing is applied if any edit does not fit
+    /// this world.
+    pub fn replay(&mut self, journal: &Journal) -> Result<(), LOError> {
+        self.commit();
+        self.check_edits(journal.transactions())?;
+        for transaction in journal.transactions() {
+            for (id, edit) in &transaction.edits {
+                let room = self.room_mut(*id).ok_or(LOError::InvalidRoomId { id: *id })?;
+                room.tilemap.redo_edits([edit])?;
+            }
+            self.commit();
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,102 @@
+use std::collections::VecDeque;
+
+use array2d::Array2D;
+
+use super::{Connectivity, TileSelection};
+
+/// How tiles outside of a selection's area, like beyond the room edge, are treated.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Edge {
+    /// Selections shrink away from the edge and have an outline along it.
+    Unselected,
+    /// Selections grow inwards from the edge and touching it does not count as a border.
+    Selected,
+}
+
+/// Operations change all tiles at once, neighbors are those of the [`Connectivity`].
+impl TileSelection {
+    fn contains_with_edge(&self, x: isize, y: isize, edge: Edge) -> bool {
+        match (usize::try_from(x), usize::try_from(y)) {
+            (Ok(x), Ok(y)) if x < self.get_width() && y < self.get_height() => self.contains(x, y),
+            _ => edge == Edge::Selected,
+        }
+    }
+
+    /// Whether any neighbor of the tile is `value`.
+    fn has_neighbor(&self, x: usize, y: usize, value: bool, connectivity: Connectivity, edge: Edge) -> bool {
+        connectivity
+            .offsets()
+            .iter()
+            .any(|(dx, dy)| self.contains_with_edge(x as isize + dx, y as isize + dy, edge) == value)
+    }
+
+    /// Adds the neighbors of selected tiles, `amount` times. Also called dilation.
+    pub fn grow(self, amount: usize, connectivity: Connectivity, edge: Edge) -> Self {
+        (0..amount).fold(self, |selection, _| {
+            let previous = selection.clone();
+            selection.predicate(|(x, y), value| value || previous.has_neighbor(x, y, true, connectivity, edge))
+        })
+    }
+
+    /// Removes selected tiles next to unselected ones, `amount` times. Also called erosion.
+    pub fn shrink(self, amount: usize, connectivity: Connectivity, edge: Edge) -> Self {
+        (0..amount).fold(self, |selection, _| {
+            let previous = selection.clone();
+            selection.predicate(|(x, y), value| value && !previous.has_neighbor(x, y, false, connectivity, edge))
+        })
+    }
+
+    /// Unselected tiles next to selected ones, like walls around a floor.
+    pub fn outline(self, connectivity: Connectivity, edge: Edge) -> Self {
+        let grown = self.clone().grow(1, connectivity, edge);
+        grown.difference(&self, (0, 0))
+    }
+
+    /// Selected tiles next to unselected ones.
+    pub fn inner_outline(self, connectivity: Connectivity, edge: Edge) -> Self {
+        let shrunk = self.clone().shrink(1, connectivity, edge);
+        self.difference(&shrunk, (0, 0))
+    }
+
+    /// Shrinks and grows back, which removes parts thinner than `2 * amount + 1` tiles.
+    pub fn open(self, amount: usize, connectivity: Connectivity, edge: Edge) -> Self {
+        self.shrink(amount, connectivity, edge).grow(amount, connectivity, edge)
+    }
+
+    /// Grows and shrinks back, which fills gaps and holes up to `2 * amount` tiles wide.
+    pub fn close(self, amount: usize, connectivity: Connectivity, edge: Edge) -> Self {
+        self.grow(amount, connectivity, edge).shrink(amount, connectivity, edge)
+    }
+
+    /// Steps from every tile to the nearest unselected one, indexed by `(row, column)`. Unselected tiles are 0, tiles
+    /// next to them 1 and so on. `None` if no tile is unselected, which needs [`Edge::Selected`].
+    pub fn distance_to_edge(&self, connectivity: Connectivity, edge: Edge) -> Array2D<Option<usize>> {
+        let (width, height) = (self.get_width(), self.get_height());
+        let mut distances = Array2D::filled_with(None, height, width);
+        let mut pending = VecDeque::new();
+        let positions = (0..height).flat_map(|y| (0..width).map(move |x| (x, y)));
+        for (x, y) in positions.clone().filter(|(x, y)| !self.contains(*x, *y)) {
+            distances[(y, x)] = Some(0);
+            pending.push_back((x, y));
+        }
+        // Queued after all unselected tiles, so distances are visited in order.
+        for (x, y) in positions.filter(|(x, y)| self.contains(*x, *y) && self.has_neighbor(*x, *y, false, connectivity, edge)) {
+            distances[(y, x)] = Some(1);
+            pending.push_back((x, y));
+        }
+
+        while let Some((x, y)) = pending.pop_front() {
+            let distance = distances[(y, x)].unwrap() + 1;
+            for (dx, dy) in connectivity.offsets() {
+                let (Some(x), Some(y)) = (x.checked_add_signed(*dx), y.checked_add_signed(*dy)) else {
+                    continue;
+                };
+                if let Some(neighbor @ None) = distances.get_mut(y, x) {
+                    *neighbor = Some(distance);
+                    pending.push_back((x, y));
+                }
+            }
+        }
+        distances
+    }
+}
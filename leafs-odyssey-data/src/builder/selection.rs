@@ -0,0 +1,434 @@
+use std::{collections::BTreeSet, ops::Range};
+
+/// Selections with at most this fraction of their area selected are stored as [`SelectionBackend::Sparse`].
+const SPARSE_DIVISOR: usize = 64;
+
+/// How a [`TileSelection`] stores its tiles. Edits switch to the backend fitting their result, so this only
+/// matters for memory use and speed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SelectionBackend {
+    /// One bit per tile of the area.
+    Bits,
+    /// Only the selected positions. Used for empty selections.
+    Sparse,
+    /// A single rectangle.
+    Rect,
+}
+
+#[derive(Clone, Debug)]
+enum Tiles {
+    /// Row major, tile `i` is bit `i % 64` of `bits[i / 64]`.
+    Bits(Vec<u64>),
+    /// `(row, column)`, so positions are ordered row major.
+    Sparse(BTreeSet<(usize, usize)>),
+    /// `(x, y, width, height)`, inside the area and never empty.
+    Rect(usize, usize, usize, usize),
+}
+
+#[derive(Clone, Debug)]
+pub struct TileSelection {
+    width: usize,
+    height: usize,
+    tiles: Tiles,
+}
+
+impl From<(usize, usize)> for TileSelection {
+    /// Selects the single tile at `(x, y)`.
+    fn from(value: (usize, usize)) -> Self {
+        Self::from((value.0, value.1, 1, 1))
+    }
+}
+
+impl From<(usize, usize, usize, usize)> for TileSelection {
+    fn from(value: (usize, usize, usize, usize)) -> Self {
+        Self::new(value.0+value.2, value.1+value.3)
+            .set_rect(value.0, value.1, value.2, value.3, true)
+    }
+}
+
+impl TileSelection {
+    pub fn from_value(width: usize, height: usize, value: bool) -> Self {
+        let tiles = match value && width > 0 && height > 0 {
+            true => Tiles::Rect(0, 0, width, height),
+            false => Tiles::Sparse(BTreeSet::new()),
+        };
+        Self { width, height, tiles }
+    }
+    /// Full selection.
+    pub fn all(width: usize, height: usize) -> Self {
+        Self::from_value(width, height, true)
+    }
+    /// Empty selection.
+    pub fn new(width: usize, height: usize) -> Self {
+        Self::from_value(width, height, false)
+    }
+
+    pub fn get_width(&self) -> usize {
+        self.width
+    }
+    pub fn get_height(&self) -> usize {
+        self.height
+    }
+    pub fn backend(&self) -> SelectionBackend {
+        match self.tiles {
+            Tiles::Bits(_) => SelectionBackend::Bits,
+            Tiles::Sparse(_) => SelectionBackend::Sparse,
+            Tiles::Rect(..) => SelectionBackend::Rect,
+        }
+    }
+
+    /// `false` outside of the area.
+    pub fn contains(&self, x: usize, y: usize) -> bool {
+        if x >= self.width || y >= self.height {
+            return false;
+        }
+        match &self.tiles {
+            Tiles::Bits(bits) => get_bit(bits, y * self.width + x),
+            Tiles::Sparse(tiles) => tiles.contains(&(y, x)),
+            Tiles::Rect(rect_x, rect_y, width, height) => {
+                (*rect_x..rect_x + width).contains(&x) && (*rect_y..rect_y + height).contains(&y)
+            }
+        }
+    }
+
+    /// Selected `(row, column)` positions in row major order.
+    pub fn get_selection(&self) -> impl DoubleEndedIterator<Item = (usize, usize)> + Clone + use<'_> {
+        match &self.tiles {
+            Tiles::Bits(bits) => SelectionIter::Bits {
+                bits,
+                width: self.width,
+                indices: 0..self.width * self.height,
+            },
+            Tiles::Sparse(tiles) => SelectionIter::Sparse(tiles.iter()),
+            Tiles::Rect(x, y, width, height) => SelectionIter::Rect {
+                x: *x,
+                y: *y,
+                width: *width,
+                indices: 0..width * height,
+            },
+        }
+    }
+
+    /// Number of selected tiles.
+    pub fn len(&self) -> usize {
+        match &self.tiles {
+            Tiles::Bits(bits) => bits.iter().map(|word| word.count_ones() as usize).sum(),
+            Tiles::Sparse(tiles) => tiles.len(),
+            Tiles::Rect(_, _, width, height) => width * height,
+        }
+    }
+    pub fn is_empty(&self) -> bool {
+        match &self.tiles {
+            Tiles::Bits(bits) => bits.iter().all(|word| *word == 0),
+            Tiles::Sparse(tiles) => tiles.is_empty(),
+            Tiles::Rect(..) => false,
+        }
+    }
+
+    /// Smallest rectangle `(x, y, width, height)` containing all selected tiles, `None` if nothing is selected.
+    pub fn bounding_box(&self) -> Option<(usize, usize, usize, usize)> {
+        if let Tiles::Rect(x, y, width, height) = self.tiles {
+            return Some((x, y, width, height));
+        }
+        let (mut left, mut top, mut right, mut bottom) = (usize::MAX, usize::MAX, 0, 0);
+        for (row, col) in self.get_selection() {
+            (left, top) = (left.min(col), top.min(row));
+            (right, bottom) = (right.max(col + 1), bottom.max(row + 1));
+        }
+        (right > 0).then(|| (left, top, right - left, bottom - top))
+    }
+
+    /// Converts to the backend. Selections which are not a rectangle can not use [`SelectionBackend::Rect`] and are
+    /// kept as they are.
+    pub fn with_backend(self, backend: SelectionBackend) -> Self {
+        let tiles = match backend {
+            SelectionBackend::Bits => Tiles::Bits(self.to_bits()),
+            SelectionBackend::Sparse => Tiles::Sparse(self.get_selection().collect()),
+            SelectionBackend::Rect => match self.as_rect() {
+                Some((x, y, width, height)) => Tiles::Rect(x, y, width, height),
+                None => return self,
+            },
+        };
+        Self { tiles, ..self }
+    }
+
+    fn sparse_limit(&self) -> usize {
+        self.width * self.height / SPARSE_DIVISOR
+    }
+
+    fn to_bits(&self) -> Vec<u64> {
+        let mut bits = vec![0; (self.width * self.height).div_ceil(64)];
+        for (row, col) in self.get_selection() {
+            set_bit(&mut bits, row * self.width + col, true);
+        }
+        bits
+    }
+
+    /// The selected tiles if they form a single rectangle.
+    fn as_rect(&self) -> Option<(usize, usize, usize, usize)> {
+        if let Tiles::Rect(x, y, width, height) = self.tiles {
+            return Some((x, y, width, height));
+        }
+        self.bounding_box().filter(|(_, _, width, height)| width * height == self.len())
+    }
+
+    /// Picks the smallest backend after changing many tiles at once.
+    fn compact(self) -> Self {
+        let count = self.get_selection().count();
+        if count == 0 {
+            return Self::new(self.width, self.height);
+        }
+        match self.as_rect() {
+            Some(_) => self.with_backend(SelectionBackend::Rect),
+            None if count <= self.sparse_limit() => self.with_backend(SelectionBackend::Sparse),
+            None => self.with_backend(SelectionBackend::Bits),
+        }
+    }
+
+    /// Changes one tile inside the area, switching backends as needed.
+    fn set_tile(&mut self, x: usize, y: usize, value: bool) {
+        if self.contains(x, y) == value {
+            return;
+        }
+        if let Tiles::Rect(_, _, width, height) = self.tiles {
+            self.tiles = match width * height <= self.sparse_limit() {
+                true => Tiles::Sparse(self.get_selection().collect()),
+                false => Tiles::Bits(self.to_bits()),
+            };
+        }
+
+        let limit = self.sparse_limit();
+        match &mut self.tiles {
+            Tiles::Bits(bits) => set_bit(bits, y * self.width + x, value),
+            Tiles::Sparse(tiles) => {
+                match value {
+                    true => tiles.insert((y, x)),
+                    false => tiles.remove(&(y, x)),
+                };
+                if tiles.len() > limit {
+                    self.tiles = Tiles::Bits(self.to_bits());
+                }
+            }
+            Tiles::Rect(..) => unreachable!(),
+        }
+    }
+
+    pub fn invert(mut self, selection: TileSelection) -> Self {
+        for (row, col) in selection.get_selection() {
+            if row < self.height && col < self.width {
+                let value = self.contains(col, row);
+                self.set_tile(col, row, !value);
+            }
+        }
+        self
+    }
+    pub fn invert_all(self) -> Self {
+        let size = (self.get_width(), self.get_height());
+        let mut bits = self.to_bits();
+        for (i, word) in bits.iter_mut().enumerate() {
+            let used = (size.0 * size.1 - i * 64).min(64);
+            *word = !*word & (u64::MAX >> (64 - used));
+        }
+        Self { tiles: Tiles::Bits(bits), ..self }.compact()
+    }
+
+    /// Selected tiles of `other` as `(x, y)` in this selection, with `other`'s top left corner at `offset`.
+    /// Tiles outside of this selection's area are skipped.
+    fn offset_positions<'a>(&self, other: &'a TileSelection, offset: (isize, isize)) -> impl Iterator<Item = (usize, usize)> + use<'a> {
+        let (width, height) = (self.width, self.height);
+        other.get_selection().filter_map(move |(row, col)| {
+            let x = usize::try_from(col as isize + offset.0).ok().filter(|x| *x < width)?;
+            let y = usize::try_from(row as isize + offset.1).ok().filter(|y| *y < height)?;
+            Some((x, y))
+        })
+    }
+
+    /// Applies the bitwise `operation` to both selections, per word for bitsets of the same size.
+    fn combine<F>(self, other: &TileSelection, offset: (isize, isize), operation: F) -> Self
+        where F: Fn(u64, u64) -> u64 {
+        if offset == (0, 0) && (self.width, self.height) == (other.width, other.height) {
+            if let (Tiles::Bits(bits), Tiles::Bits(other_bits)) = (&self.tiles, &other.tiles) {
+                let bits = bits.iter().zip(other_bits).map(|(word, other_word)| operation(*word, *other_word)).collect();
+                return Self { tiles: Tiles::Bits(bits), ..self }.compact();
+            }
+        }
+
+        // Only tiles of `other` change, unless unselected tiles of `other` deselect everything.
+        let mut result = match operation(1, 0) & 1 != 0 {
+            true => self.clone(),
+            false => Self::new(self.width, self.height),
+        };
+        for (x, y) in self.offset_positions(other, offset) {
+            result.set_tile(x, y, operation(self.contains(x, y) as u64, 1) & 1 != 0);
+        }
+        result.compact()
+    }
+
+    /// Tiles selected in either selection. `other` may have a different size and is placed with its top left corner
+    /// at `offset`, tiles outside of this selection are ignored. The same goes for the other set operations.
+    pub fn union(self, other: &TileSelection, offset: (isize, isize)) -> Self {
+        self.combine(other, offset, |value, other| value | other)
+    }
+    /// Tiles selected in both selections.
+    pub fn intersection(self, other: &TileSelection, offset: (isize, isize)) -> Self {
+        self.combine(other, offset, |value, other| value & other)
+    }
+    /// Tiles selected in this selection but not in `other`.
+    pub fn difference(self, other: &TileSelection, offset: (isize, isize)) -> Self {
+        self.combine(other, offset, |value, other| value & !other)
+    }
+    /// Tiles selected in exactly one of the selections.
+    pub fn symmetric_difference(self, other: &TileSelection, offset: (isize, isize)) -> Self {
+        self.combine(other, offset, |value, other| value ^ other)
+    }
+
+    pub fn set_all(self, value: bool) -> Self {
+        Self::from_value(self.width, self.height, value)
+    }
+    pub fn add_all(self) -> Self {
+        self.set_all(true)
+    }
+    pub fn remove_all(self) -> Self {
+        self.set_all(false)
+    }
+
+    pub fn set(mut self, x: usize, y: usize, value: bool) -> Self {
+        if x < self.width && y < self.height {
+            self.set_tile(x, y, value);
+        }
+        self
+    }
+    pub fn add(self, x: usize, y: usize) -> Self {
+        self.set(x, y, true)
+    }
+    pub fn remove(self, x: usize, y: usize) -> Self {
+        self.set(x, y, false)
+    }
+
+    pub fn predicate<F>(self, predicate: F) -> Self
+        where F: Fn((usize, usize), bool) -> bool {
+        let mut bits = vec![0; (self.width * self.height).div_ceil(64)];
+        for y in 0..self.height {
+            for x in 0..self.width {
+                set_bit(&mut bits, y * self.width + x, predicate((x, y), self.contains(x, y)));
+            }
+        }
+        Self { tiles: Tiles::Bits(bits), ..self }.compact()
+    }
+
+    /// Keeps the selected tiles matching the predicate. Only selected tiles are visited and sparse selections stay
+    /// sparse.
+    pub fn predicate_and<F>(mut self, predicate: F) -> Self
+        where F: Fn(usize, usize) -> bool {
+        let rejected: Vec<_> = self.get_selection().filter(|(row, col)| !predicate(*col, *row)).collect();
+        if rejected.is_empty() {
+            return self;
+        }
+        let sparse = matches!(self.tiles, Tiles::Sparse(_));
+        for (row, col) in rejected {
+            self.set_tile(col, row, false);
+        }
+        match sparse {
+            true => self,
+            false => self.compact(),
+        }
+    }
+
+    pub fn predicate_or<F>(self, predicate: F) -> Self
+        where F: Fn(usize, usize) -> bool {
+        self.predicate(|(x, y), value| {
+            if value { return true;}
+            predicate(x, y)
+        })
+    }
+
+    pub fn set_rect(mut self, x: usize, y: usize, width: usize, height: usize, value: bool) -> Self {
+        let (right, bottom) = ((x + width).min(self.width), (y + height).min(self.height));
+        if x >= right || y >= bottom {
+            return self;
+        }
+        let covers_selection = match self.tiles {
+            Tiles::Sparse(ref tiles) => tiles.is_empty(),
+            Tiles::Rect(rect_x, rect_y, rect_width, rect_height) => {
+                x <= rect_x && y <= rect_y && rect_x + rect_width <= right && rect_y + rect_height <= bottom
+            }
+            Tiles::Bits(_) => false,
+        };
+        match (covers_selection, value) {
+            (true, true) => self.tiles = Tiles::Rect(x, y, right - x, bottom - y),
+            (true, false) => self.tiles = Tiles::Sparse(BTreeSet::new()),
+            _ => {
+                for y in y..bottom {
+                    for x in x..right {
+                        self.set_tile(x, y, value);
+                    }
+                }
+            }
+        }
+        self
+    }
+    pub fn add_rect(self, x: usize, y: usize, width: usize, height: usize) -> Self {
+        self.set_rect(x, y, width, height, true)
+    }
+    pub fn remove_rect(self, x: usize, y: usize, width: usize, height: usize) -> Self {
+        self.set_rect(x, y, width, height, false)
+    }
+}
+
+fn get_bit(bits: &[u64], index: usize) -> bool {
+    bits[index / 64] & (1 << (index % 64)) != 0
+}
+
+fn set_bit(bits: &mut [u64], index: usize, value: bool) {
+    match value {
+        true => bits[index / 64] |= 1 << (index % 64),
+        false => bits[index / 64] &= !(1 << (index % 64)),
+    }
+}
+
+/// Iterator of [`TileSelection::get_selection`] for every backend.
+#[derive(Clone)]
+enum SelectionIter<'a> {
+    Bits {
+        bits: &'a [u64],
+        width: usize,
+        indices: Range<usize>,
+    },
+    Sparse(std::collections::btree_set::Iter<'a, (usize, usize)>),
+    Rect {
+        x: usize,
+        y: usize,
+        width: usize,
+        indices: Range<usize>,
+    },
+}
+
+impl Iterator for SelectionIter<'_> {
+    type Item = (usize, usize);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            Self::Bits { bits, width, indices } => {
+                let index = indices.find(|index| get_bit(bits, *index))?;
+                Some((index / *width, index % *width))
+            }
+            Self::Sparse(tiles) => tiles.next().copied(),
+            Self::Rect { x, y, width, indices } => indices.next().map(|index| (*y + index / *width, *x + index % *width)),
+        }
+    }
+}
+
+impl DoubleEndedIterator for SelectionIter<'_> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        match self {
+            Self::Bits { bits, width, indices } => {
+                let index = indices.rfind(|index| get_bit(bits, *index))?;
+                Some((index / *width, index % *width))
+            }
+            Self::Sparse(tiles) => tiles.next_back().copied(),
+            Self::Rect { x, y, width, indices } => {
+                indices.next_back().map(|index| (*y + index / *width, *x + index % *width))
+            }
+        }
+    }
+}
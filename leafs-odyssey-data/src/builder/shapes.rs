@@ -0,0 +1,217 @@
+use super::TileSelection;
+
+/// Tiles of a line including both ends, using Bresenham's line algorithm.
+pub(super) fn line_points(from: (isize, isize), to: (isize, isize)) -> Vec<(isize, isize)> {
+    let (mut x, mut y) = from;
+    let dx = (to.0 - x).abs();
+    let dy = -(to.1 - y).abs();
+    let step_x = if x < to.0 { 1 } else { -1 };
+    let step_y = if y < to.1 { 1 } else { -1 };
+    let mut error = dx + dy;
+    let mut points = vec![];
+    loop {
+        points.push((x, y));
+        if (x, y) == to {
+            return points;
+        }
+        let error2 = 2 * error;
+        if error2 >= dy {
+            error += dy;
+            x += step_x;
+        }
+        if error2 <= dx {
+            error += dx;
+            y += step_y;
+        }
+    }
+}
+
+fn signed(position: (usize, usize)) -> (isize, isize) {
+    (position.0 as isize, position.1 as isize)
+}
+
+/// Whether the tile is inside an ellipse around `(0, 0)`. Radii are widened by half a tile, so a radius of 0
+/// selects only the center and shapes look round.
+fn in_ellipse(dx: isize, dy: isize, radius_x: usize, radius_y: usize) -> bool {
+    let x = dx as f64 / (radius_x as f64 + 0.5);
+    let y = dy as f64 / (radius_y as f64 + 0.5);
+    x * x + y * y <= 1.0
+}
+
+/// Even-odd rule for the center of the tile.
+fn in_polygon(x: isize, y: isize, points: &[(isize, isize)]) -> bool {
+    let mut inside = false;
+    for (i, &(x1, y1)) in points.iter().enumerate() {
+        let (x2, y2) = points[(i + 1) % points.len()];
+        if (y1 > y) != (y2 > y) {
+            let crossing = x1 as f64 + (y - y1) as f64 * (x2 - x1) as f64 / (y2 - y1) as f64;
+            if (x as f64) < crossing {
+                inside = !inside;
+            }
+        }
+    }
+    inside
+}
+
+/// Shapes are clipped to the selection's area. Positions are `(x, y)`.
+impl TileSelection {
+    fn set_signed(self, position: (isize, isize), value: bool) -> Self {
+        match (usize::try_from(position.0), usize::try_from(position.1)) {
+            (Ok(x), Ok(y)) => self.set(x, y, value),
+            _ => self,
+        }
+    }
+
+    /// Applies `value` to every tile in the area of `radius_x` x `radius_y` around `center` matching `predicate`.
+    fn set_around<F>(mut self, center: (usize, usize), radius_x: usize, radius_y: usize, value: bool, predicate: F) -> Self
+        where F: Fn(isize, isize) -> bool {
+        let center = signed(center);
+        let (radius_x, radius_y) = (radius_x as isize, radius_y as isize);
+        for dy in -radius_y..=radius_y {
+            for dx in -radius_x..=radius_x {
+                if predicate(dx, dy) {
+                    self = self.set_signed((center.0 + dx, center.1 + dy), value);
+                }
+            }
+        }
+        self
+    }
+
+    /// Line including both ends.
+    pub fn set_line(self, from: (usize, usize), to: (usize, usize), value: bool) -> Self {
+        line_points(signed(from), signed(to))
+            .into_iter()
+            .fold(self, |selection, position| selection.set_signed(position, value))
+    }
+    pub fn add_line(self, from: (usize, usize), to: (usize, usize)) -> Self {
+        self.set_line(from, to, true)
+    }
+    pub fn remove_line(self, from: (usize, usize), to: (usize, usize)) -> Self {
+        self.set_line(from, to, false)
+    }
+
+    /// Filled ellipse. A radius of 0 is a single tile wide.
+    pub fn set_ellipse(self, center: (usize, usize), radius_x: usize, radius_y: usize, value: bool) -> Self {
+        self.set_around(center, radius_x, radius_y, value, |dx, dy| in_ellipse(dx, dy, radius_x, radius_y))
+    }
+    pub fn add_ellipse(self, center: (usize, usize), radius_x: usize, radius_y: usize) -> Self {
+        self.set_ellipse(center, radius_x, radius_y, true)
+    }
+    pub fn remove_ellipse(self, center: (usize, usize), radius_x: usize, radius_y: usize) -> Self {
+        self.set_ellipse(center, radius_x, radius_y, false)
+    }
+
+    /// Tiles of the filled ellipse next to a tile outside of it, which forms a closed outline.
+    pub fn set_ellipse_outline(self, center: (usize, usize), radius_x: usize, radius_y: usize, value: bool) -> Self {
+        self.set_around(center, radius_x, radius_y, value, |dx, dy| {
+            in_ellipse(dx, dy, radius_x, radius_y)
+                && [(-1, 0), (1, 0), (0, -1), (0, 1)]
+                    .into_iter()
+                    .any(|(x, y)| !in_ellipse(dx + x, dy + y, radius_x, radius_y))
+        })
+    }
+    pub fn add_ellipse_outline(self, center: (usize, usize), radius_x: usize, radius_y: usize) -> Self {
+        self.set_ellipse_outline(center, radius_x, radius_y, true)
+    }
+    pub fn remove_ellipse_outline(self, center: (usize, usize), radius_x: usize, radius_y: usize) -> Self {
+        self.set_ellipse_outline(center, radius_x, radius_y, false)
+    }
+
+    /// Filled circle, see [`Self::set_ellipse`].
+    pub fn set_circle(self, center: (usize, usize), radius: usize, value: bool) -> Self {
+        self.set_ellipse(center, radius, radius, value)
+    }
+    pub fn add_circle(self, center: (usize, usize), radius: usize) -> Self {
+        self.set_circle(center, radius, true)
+    }
+    pub fn remove_circle(self, center: (usize, usize), radius: usize) -> Self {
+        self.set_circle(center, radius, false)
+    }
+
+    /// See [`Self::set_ellipse_outline`].
+    pub fn set_circle_outline(self, center: (usize, usize), radius: usize, value: bool) -> Self {
+        self.set_ellipse_outline(center, radius, radius, value)
+    }
+    pub fn add_circle_outline(self, center: (usize, usize), radius: usize) -> Self {
+        self.set_circle_outline(center, radius, true)
+    }
+    pub fn remove_circle_outline(self, center: (usize, usize), radius: usize) -> Self {
+        self.set_circle_outline(center, radius, false)
+    }
+
+    /// Filled polygon through the points, including its edges. The last point is connected to the first one.
+    pub fn set_polygon(self, points: &[(usize, usize)], value: bool) -> Self {
+        let points: Vec<_> = points.iter().copied().map(signed).collect();
+        let (Some(left), Some(right)) = (points.iter().map(|p| p.0).min(), points.iter().map(|p| p.0).max()) else {
+            return self;
+        };
+        let top = points.iter().map(|p| p.1).min().unwrap();
+        let bottom = points.iter().map(|p| p.1).max().unwrap();
+
+        let mut selection = self;
+        for y in top..=bottom {
+            for x in left..=right {
+                if in_polygon(x, y, &points) {
+                    selection = selection.set_signed((x, y), value);
+                }
+            }
+        }
+        selection.set_polygon_edges(&points, value)
+    }
+    pub fn add_polygon(self, points: &[(usize, usize)]) -> Self {
+        self.set_polygon(points, true)
+    }
+    pub fn remove_polygon(self, points: &[(usize, usize)]) -> Self {
+        self.set_polygon(points, false)
+    }
+
+    /// Lines between the points. The last point is connected to the first one.
+    pub fn set_polygon_outline(self, points: &[(usize, usize)], value: bool) -> Self {
+        let points: Vec<_> = points.iter().copied().map(signed).collect();
+        self.set_polygon_edges(&points, value)
+    }
+    pub fn add_polygon_outline(self, points: &[(usize, usize)]) -> Self {
+        self.set_polygon_outline(points, true)
+    }
+    pub fn remove_polygon_outline(self, points: &[(usize, usize)]) -> Self {
+        self.set_polygon_outline(points, false)
+    }
+
+    fn set_polygon_edges(mut self, points: &[(isize, isize)], value: bool) -> Self {
+        for (i, from) in points.iter().enumerate() {
+            for position in line_points(*from, points[(i + 1) % points.len()]) {
+                self = self.set_signed(position, value);
+            }
+        }
+        self
+    }
+
+    /// Border of the rectangle at `(x, y)`, `thickness` tiles wide towards the inside.
+    pub fn set_rect_border(self, x: usize, y: usize, width: usize, height: usize, thickness: usize, value: bool) -> Self {
+        let inner = (width.saturating_sub(2 * thickness), height.saturating_sub(2 * thickness));
+        if inner.0 == 0 || inner.1 == 0 {
+            return self.set_rect(x, y, width, height, value);
+        }
+        self.set_rect(x, y, width, thickness, value)
+            .set_rect(x, y + height - thickness, width, thickness, value)
+            .set_rect(x, y + thickness, thickness, inner.1, value)
+            .set_rect(x + width - thickness, y + thickness, thickness, inner.1, value)
+    }
+    pub fn add_rect_border(self, x: usize, y: usize, width: usize, height: usize, thickness: usize) -> Self {
+        self.set_rect_border(x, y, width, height, thickness, true)
+    }
+    pub fn remove_rect_border(self, x: usize, y: usize, width: usize, height: usize, thickness: usize) -> Self {
+        self.set_rect_border(x, y, width, height, thickness, false)
+    }
+
+    /// Tiles at most `radius` steps (Manhattan distance) away from `center`.
+    pub fn set_diamond(self, center: (usize, usize), radius: usize, value: bool) -> Self {
+        self.set_around(center, radius, radius, value, |dx, dy| dx.unsigned_abs() + dy.unsigned_abs() <= radius)
+    }
+    pub fn add_diamond(self, center: (usize, usize), radius: usize) -> Self {
+        self.set_diamond(center, radius, true)
+    }
+    pub fn remove_diamond(self, center: (usize, usize), radius: usize) -> Self {
+        self.set_diamond(center, radius, false)
+    }
+}
@@ -0,0 +1,161 @@
+use crate::{data::LORoomInfo, error::LOError};
+
+use super::{Room, World};
+
+/// Direction from a room to its neighbor. +X is east, +Y is south, +Z is up.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Direction {
+    North,
+    East,
+    South,
+    West,
+    Up,
+    Down,
+}
+
+impl Direction {
+    pub const ALL: [Self; 6] = [Self::North, Self::East, Self::South, Self::West, Self::Up, Self::Down];
+
+    /// Unit step in world coordinates.
+    pub fn offset(&self) -> (i16, i16, i16) {
+        match self {
+            Self::North => (0, -1, 0),
+            Self::East => (1, 0, 0),
+            Self::South => (0, 1, 0),
+            Self::West => (-1, 0, 0),
+            Self::Up => (0, 0, 1),
+            Self::Down => (0, 0, -1),
+        }
+    }
+
+    pub fn opposite(&self) -> Self {
+        match self {
+            Self::North => Self::South,
+            Self::East => Self::West,
+            Self::South => Self::North,
+            Self::West => Self::East,
+            Self::Up => Self::Down,
+            Self::Down => Self::Up,
+        }
+    }
+}
+
+/// Whether the half-open ranges share at least one tile.
+fn ranges_overlap(start1: i32, length1: u16, start2: i32, length2: u16) -> bool {
+    start1 < start2 + length2 as i32 && start2 < start1 + length1 as i32
+}
+
+/// Whether both areas are on the same floor and share at least one tile.
+fn areas_overlap(position1: (i16, i16, i16), size1: (u16, u16), position2: (i16, i16, i16), size2: (u16, u16)) -> bool {
+    position1.2 == position2.2
+        && ranges_overlap(position1.0 as i32, size1.0, position2.0 as i32, size2.0)
+        && ranges_overlap(position1.1 as i32, size1.1, position2.1 as i32, size2.1)
+}
+
+impl LORoomInfo {
+    /// Like [`Room::overlaps`], for rooms which were not converted to the builder.
+    pub fn overlaps(&self, other: &LORoomInfo) -> bool {
+        areas_overlap(
+            (self.x_position, self.y_position, self.z_position),
+            (self.width, self.height),
+            (other.x_position, other.y_position, other.z_position),
+            (other.width, other.height),
+        )
+    }
+}
+
+impl Room {
+    fn x_range(&self) -> (i32, u16) {
+        (self.position.0 as i32, self.width)
+    }
+    fn y_range(&self) -> (i32, u16) {
+        (self.position.1 as i32, self.height)
+    }
+
+    /// Position of a world tile coordinate inside the room, if the room contains it.
+    pub fn local_position(&self, position: (i16, i16, i16)) -> Option<(usize, usize)> {
+        let x = position.0 as i32 - self.position.0 as i32;
+        let y = position.1 as i32 - self.position.1 as i32;
+        let inside = position.2 == self.position.2
+            && (0..self.width as i32).contains(&x)
+            && (0..self.height as i32).contains(&y);
+        inside.then_some((x as usize, y as usize))
+    }
+
+    /// World tile coordinate of a position inside the room, `None` if it does not fit into `i16`.
+    pub fn world_position(&self, x: usize, y: usize) -> Option<(i16, i16, i16)> {
+        let x = self.position.0.checked_add(i16::try_from(x).ok()?)?;
+        let y = self.position.1.checked_add(i16::try_from(y).ok()?)?;
+        Some((x, y, self.position.2))
+    }
+
+    /// Whether both rooms are on the same floor and share at least one tile.
+    pub fn overlaps(&self, other: &Room) -> bool {
+        areas_overlap(self.position, (self.width, self.height), other.position, (other.width, other.height))
+    }
+
+    /// Whether `other` shares (part of) the side of the room in `direction`.
+    pub fn is_neighbor(&self, other: &Room, direction: Direction) -> bool {
+        let (x1, width1) = self.x_range();
+        let (y1, height1) = self.y_range();
+        let (x2, width2) = other.x_range();
+        let (y2, height2) = other.y_range();
+        let z1 = self.position.2 as i32;
+        let z2 = other.position.2 as i32;
+        match direction {
+            Direction::North => z1 == z2 && y2 + height2 as i32 == y1 && ranges_overlap(x1, width1, x2, width2),
+            Direction::South => z1 == z2 && y1 + height1 as i32 == y2 && ranges_overlap(x1, width1, x2, width2),
+            Direction::West => z1 == z2 && x2 + width2 as i32 == x1 && ranges_overlap(y1, height1, y2, height2),
+            Direction::East => z1 == z2 && x1 + width1 as i32 == x2 && ranges_overlap(y1, height1, y2, height2),
+            Direction::Up | Direction::Down => {
+                z2 - z1 == direction.offset().2 as i32
+                    && ranges_overlap(x1, width1, x2, width2)
+                    && ranges_overlap(y1, height1, y2, height2)
+            }
+        }
+    }
+}
+
+impl World {
+    /// Room containing the world tile coordinate, and the position of the tile inside it.
+    /// If rooms overlap, the first one in `rooms` is returned.
+    pub fn room_at(&self, position: (i16, i16, i16)) -> Option<(&Room, (usize, usize))> {
+        self.rooms
+            .iter()
+            .find_map(|room| room.local_position(position).map(|local| (room, local)))
+    }
+
+    pub fn room_at_mut(&mut self, position: (i16, i16, i16)) -> Option<(&mut Room, (usize, usize))> {
+        self.rooms
+            .iter_mut()
+            .find_map(|room| room.local_position(position).map(|local| (room, local)))
+    }
+
+    /// Rooms sharing the side of the room in `direction`. Rooms of different sizes may have several neighbors per side.
+    pub fn neighbors(&self, id: u32, direction: Direction) -> Result<Vec<&Room>, LOError> {
+        let room = self.room(id).ok_or(LOError::InvalidRoomId { id })?;
+        Ok(self.rooms.iter().filter(|other| room.is_neighbor(other, direction)).collect())
+    }
+
+    /// Ids of the rooms sharing tiles with `room`, except itself.
+    pub fn rooms_overlapping(&self, room: &Room) -> Vec<u32> {
+        self.rooms
+            .iter()
+            .filter(|other| other.id != room.id && room.overlaps(other))
+            .map(|other| other.id)
+            .collect()
+    }
+
+    /// Pairs of ids of rooms sharing tiles.
+    pub fn overlapping_rooms(&self) -> Vec<(u32, u32)> {
+        let mut pairs = vec![];
+        for (i, room) in self.rooms.iter().enumerate() {
+            for other in &self.rooms[i + 1..] {
+                if room.overlaps(other) {
+                    pairs.push((room.id, other.id));
+                }
+            }
+        }
+        pairs
+    }
+}
@@ -0,0 +1,69 @@
+use crate::{
+    data::{LOStackDirection, LOStackElement, LOStackTile, LOTile},
+    error::LOError,
+};
+
+use super::connections::to_connection;
+
+/// Builds a `LOTile::Stack`, which is written on `Tilemap::LAYER5`. Elements are stored in the order they are pushed.
+#[derive(Clone, Debug, Default)]
+pub struct StackBuilder {
+    elements: Vec<LOStackElement>,
+    warnings: Vec<String>,
+}
+
+impl StackBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Converts the tile like `LOStackTile::try_from`, keeping the connections of toggle switches.
+    /// Adds a warning for tiles which are not officially supported in stacks, see [`Self::warnings`].
+    pub fn push(mut self, tile: &LOTile, direction: LOStackDirection) -> Result<Self, LOError> {
+        let stack_tile = LOStackTile::try_from(tile)?;
+        if !stack_tile.is_officially_supported() {
+            self.warnings.push(format!("Stacked {:?} is not officially supported!", stack_tile));
+        }
+
+        let connections = match tile {
+            LOTile::ToggleSwitch { connections } => connections.clone(),
+            _ => vec![],
+        };
+        self.elements.push(LOStackElement::new(stack_tile, direction, connections));
+        Ok(self)
+    }
+
+    /// Connects the last pushed element, which has to be a toggle switch, to the position. Fails with
+    /// `LOError::InvalidPosition` if the position does not fit into a connection.
+    pub fn connect(mut self, target: (usize, usize)) -> Result<Self, LOError> {
+        match self.elements.last_mut() {
+            Some(element) if element.tile == LOStackTile::ToggleSwitch => {
+                let connection = to_connection(target)?;
+                if !element.connections().contains(&connection) {
+                    element.connections_mut().push(connection);
+                }
+                Ok(self)
+            }
+            element => Err(LOError::UnexpectedTile {
+                tile: element.map_or(LOTile::None, |element| LOTile::from(&*element)),
+                expected: "a toggle switch",
+            }),
+        }
+    }
+
+    pub fn elements(&self) -> &[LOStackElement] {
+        &self.elements
+    }
+
+    /// Problems with the pushed elements which do not prevent building the stack, in the order they were found.
+    pub fn warnings(&self) -> &[String] {
+        &self.warnings
+    }
+
+    pub fn build(self) -> LOTile {
+        LOTile::Stack {
+            tiles: self.elements,
+            unknown1: 0,
+        }
+    }
+}
@@ -0,0 +1,180 @@
+use array2d::Array2D;
+
+use crate::{
+    data::{LOConnection, LOTile},
+    error::LOError,
+};
+
+use super::{TileConnection, TileSelection, Tilemap, World};
+
+/// Tiles of all five layers, copied from a region of a tilemap to be pasted elsewhere.
+/// Positions are relative to the top left corner of the region's bounding box.
+#[derive(Clone, Debug)]
+pub struct Stamp {
+    /// Positions of the bounding box which are part of the region. Others are left unchanged when pasting.
+    selection: TileSelection,
+    layers: [Array2D<LOTile>; 5],
+    external_connections: Vec<TileConnection>,
+}
+
+impl Stamp {
+    pub fn get_width(&self) -> usize {
+        self.selection.get_width()
+    }
+    pub fn get_height(&self) -> usize {
+        self.selection.get_height()
+    }
+
+    pub fn contains(&self, x: usize, y: usize) -> bool {
+        self.selection.contains(x, y)
+    }
+
+    /// `None` outside of the region.
+    pub fn get_tile(&self, layer: u8, x: usize, y: usize) -> Option<&LOTile> {
+        match self.contains(x, y) {
+            true => self.layers.get(layer as usize)?.get(y, x),
+            false => None,
+        }
+    }
+
+    /// Connections of copied triggers to tiles outside of the region, which are not part of the stamp.
+    /// Triggers are in stamp positions, targets in positions of the copied tilemap.
+    pub fn external_connections(&self) -> &[TileConnection] {
+        &self.external_connections
+    }
+
+    /// Copy of the tile with its connections moved to `target`, or removed where `target` returns `None`.
+    fn placed_tile<F>(&self, layer: usize, x: usize, y: usize, mut target: F) -> LOTile
+    where
+        F: FnMut((usize, usize)) -> Option<(usize, usize)>,
+    {
+        let mut tile = self.layers[layer][(y, x)].clone();
+        let mut targets = vec![];
+        tile.retain_connections(|connection| {
+            let position = target((connection.x_position as usize, connection.y_position as usize));
+            targets.extend(position);
+            position.is_some()
+        });
+        for (connection, (x, y)) in tile.connections_mut().into_iter().zip(targets) {
+            *connection = LOConnection::new(x as u16, y as u16);
+        }
+        tile
+    }
+
+    fn positions(&self) -> impl Iterator<Item = (usize, usize)> + use<'_> {
+        self.selection.get_selection().map(|(row, column)| (column, row))
+    }
+}
+
+impl Tilemap {
+    /// Copies the selected tiles of all layers into a stamp. Connections of copied triggers are kept if their
+    /// target is copied as well, others are listed in [`Stamp::external_connections`].
+    pub fn copy(&self, selection: &TileSelection) -> Stamp {
+        let positions: Vec<_> = selection.get_selection().map(|(row, column)| (column, row)).collect();
+        let left = positions.iter().map(|position| position.0).min().unwrap_or(0);
+        let top = positions.iter().map(|position| position.1).min().unwrap_or(0);
+        let right = positions.iter().map(|position| position.0 + 1).max().unwrap_or(0);
+        let bottom = positions.iter().map(|position| position.1 + 1).max().unwrap_or(0);
+        let (width, height) = (right - left, bottom - top);
+
+        let stamp_selection = positions
+            .iter()
+            .fold(TileSelection::new(width, height), |stamp_selection, (x, y)| stamp_selection.add(x - left, y - top));
+        let inside = |(x, y): (usize, usize)| {
+            let position = (x.checked_sub(left)?, y.checked_sub(top)?);
+            stamp_selection.contains(position.0, position.1).then_some(position)
+        };
+
+        let mut external_connections = vec![];
+        let layers: Vec<_> = Tilemap::LAYER_INDICES
+            .map(|layer| {
+                let source = self.get_layer(layer).unwrap();
+                let mut tiles = Array2D::filled_with(LOTile::None, height, width);
+                for &(x, y) in &positions {
+                    let Some(mut tile) = source.get(y, x).cloned() else {
+                        continue;
+                    };
+                    let trigger = (x - left, y - top);
+                    tile.retain_connections(|connection| {
+                        let target = (connection.x_position as usize, connection.y_position as usize);
+                        let keep = inside(target).is_some();
+                        if !keep {
+                            external_connections.push(TileConnection { layer, trigger, target });
+                        }
+                        keep
+                    });
+                    for connection in tile.connections_mut() {
+                        connection.x_position -= left as u16;
+                        connection.y_position -= top as u16;
+                    }
+                    tiles[(trigger.1, trigger.0)] = tile;
+                }
+                tiles
+            })
+            .collect();
+
+        Stamp {
+            selection: stamp_selection,
+            layers: layers.try_into().unwrap(),
+            external_connections,
+        }
+    }
+
+    pub fn copy_rect(&self, x: usize, y: usize, width: usize, height: usize) -> Stamp {
+        self.copy(&self.select().add_rect(x, y, width, height))
+    }
+
+    /// Replaces the tiles of all layers with those of the stamp, with its top left corner at `position`.
+    /// The stamp has to fit inside the tilemap.
+    pub fn paste(&mut self, stamp: &Stamp, position: (usize, usize)) -> Result<(), LOError> {
+        let (right, bottom) = (position.0 + stamp.get_width(), position.1 + stamp.get_height());
+        if stamp.get_width() > 0 && (right > self.get_width() as usize || bottom > self.get_height() as usize) {
+            return Err(LOError::InvalidPosition { x: right - 1, y: bottom - 1 });
+        }
+
+        for layer in Tilemap::LAYER_INDICES {
+            for (x, y) in stamp.positions() {
+                let tile = stamp.placed_tile(layer as usize, x, y, |(x, y)| Some((position.0 + x, position.1 + y)));
+                self.set_tile_on_layer(layer, (position.0 + x, position.1 + y), tile)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl World {
+    /// Replaces the tiles of all layers with those of the stamp, with its top left corner at `position`. Missing
+    /// rooms are created. Returns connections which were dropped, as their trigger and target ended up in different
+    /// rooms, in stamp positions.
+    pub fn paste(&mut self, stamp: &Stamp, position: (i16, i16, i16)) -> Result<Vec<TileConnection>, LOError> {
+        let world_position = |(x, y): (usize, usize)| {
+            let offset = |start: i16, offset: usize| i16::try_from(offset).ok().and_then(|offset| start.checked_add(offset));
+            match (offset(position.0, x), offset(position.1, y)) {
+                (Some(x), Some(y)) => Some((x, y, position.2)),
+                _ => None,
+            }
+        };
+        let positions = stamp
+            .positions()
+            .map(world_position)
+            .collect::<Option<Vec<_>>>()
+            .ok_or(LOError::InvalidWorldPosition { position })?;
+        let rooms = self.room_indices_at_or_create(positions)?;
+
+        let mut dropped = vec![];
+        for ((x, y), (index, local)) in stamp.positions().zip(rooms) {
+            let room = &mut self.rooms[index];
+            for layer in Tilemap::LAYER_INDICES {
+                let tile = stamp.placed_tile(layer as usize, x, y, |target| {
+                    let local = world_position(target).and_then(|target| room.local_position(target));
+                    if local.is_none() {
+                        dropped.push(TileConnection { layer, trigger: (x, y), target });
+                    }
+                    local
+                });
+                room.tilemap.set_tile_on_layer(layer, local, tile)?;
+            }
+        }
+        Ok(dropped)
+    }
+}
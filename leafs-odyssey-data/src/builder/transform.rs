@@ -0,0 +1,132 @@
+use array2d::Array2D;
+
+use crate::data::{LOConnection, LODirection, LOStackDirection, LOTile};
+
+use super::{Room, Tilemap};
+
+/// Rotations are clockwise, as seen in the game.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Transform {
+    Rotate90,
+    Rotate180,
+    Rotate270,
+    /// Swaps left and right.
+    FlipHorizontal,
+    /// Swaps up and down.
+    FlipVertical,
+}
+
+impl Transform {
+    /// Size of a `width` x `height` area after the transform.
+    pub fn size(&self, width: usize, height: usize) -> (usize, usize) {
+        match self {
+            Self::Rotate90 | Self::Rotate270 => (height, width),
+            Self::Rotate180 | Self::FlipHorizontal | Self::FlipVertical => (width, height),
+        }
+    }
+
+    /// Where the position ends up in a `width` x `height` area. Expects the position to be inside the area.
+    pub fn position(&self, position: (usize, usize), width: usize, height: usize) -> (usize, usize) {
+        let (x, y) = position;
+        match self {
+            Self::Rotate90 => (height - 1 - y, x),
+            Self::Rotate180 => (width - 1 - x, height - 1 - y),
+            Self::Rotate270 => (y, width - 1 - x),
+            Self::FlipHorizontal => (width - 1 - x, y),
+            Self::FlipVertical => (x, height - 1 - y),
+        }
+    }
+
+    /// Directions are numbered clockwise starting at up, in `LODirection` as well as `LOStackDirection`.
+    fn turn(&self, direction: u8) -> u8 {
+        match self {
+            Self::Rotate90 => (direction + 1) % 4,
+            Self::Rotate180 => (direction + 2) % 4,
+            Self::Rotate270 => (direction + 3) % 4,
+            Self::FlipHorizontal => (4 - direction) % 4,
+            Self::FlipVertical => (6 - direction) % 4,
+        }
+    }
+
+    pub fn direction(&self, direction: &LODirection) -> LODirection {
+        match self.turn(direction.clone() as u8) {
+            0 => LODirection::Up,
+            1 => LODirection::Right,
+            2 => LODirection::Down,
+            _ => LODirection::Left,
+        }
+    }
+
+    pub fn stack_direction(&self, direction: &LOStackDirection) -> LOStackDirection {
+        self.direction(&direction.clone().into()).into()
+    }
+
+    /// Turns the directions of the tile and moves its connections, which are inside a `width` x `height` tilemap.
+    /// Connections outside of the tilemap are kept as-is.
+    pub fn tile(&self, tile: &mut LOTile, width: usize, height: usize) {
+        match tile {
+            LOTile::StartPoint { direction }
+            | LOTile::BombBug { direction }
+            | LOTile::Slug { direction }
+            | LOTile::FlyingSnake { direction } => *direction = self.direction(direction),
+            LOTile::Stack { tiles, .. } => {
+                for element in tiles {
+                    element.direction = self.stack_direction(&element.direction);
+                }
+            }
+            _ => {}
+        }
+
+        for connection in tile.connections_mut() {
+            let position = (connection.x_position as usize, connection.y_position as usize);
+            if position.0 < width && position.1 < height {
+                let (x, y) = self.position(position, width, height);
+                *connection = LOConnection::new(x as u16, y as u16);
+            }
+        }
+    }
+
+    fn layer(&self, layer: &Array2D<LOTile>, width: usize, height: usize) -> Array2D<LOTile> {
+        let (layer_width, layer_height) = (layer.num_columns(), layer.num_rows());
+        let (new_width, new_height) = self.size(layer_width, layer_height);
+        let mut result = Array2D::filled_with(LOTile::None, new_height, new_width);
+        for ((y, x), tile) in layer.enumerate_row_major() {
+            let mut tile = tile.clone();
+            self.tile(&mut tile, width, height);
+            let (new_x, new_y) = self.position((x, y), layer_width, layer_height);
+            result.set(new_y, new_x, tile).unwrap();
+        }
+        result
+    }
+}
+
+impl Tilemap {
+    /// Transforms all layers, including directions and connections of tiles. The transform itself is not recorded by
+    /// [`Self::record_edits`], so edits recorded before it no longer match the tilemap.
+    pub fn transform(self, transform: Transform) -> Self {
+        let (width, height) = (self.get_width() as usize, self.get_height() as usize);
+        let (layer1, layer2, layer3, layer4, layer5) = &self.layers;
+        Self {
+            layers: (
+                transform.layer(layer1, width, height),
+                transform.layer(layer2, width, height),
+                transform.layer(layer3, width, height),
+                transform.layer(layer4, width, height),
+                transform.layer(layer5, width, height),
+            ),
+            recorded_edits: self.recorded_edits,
+        }
+    }
+}
+
+impl Room {
+    /// Transforms the tilemap, swapping width and height for quarter turns. The position stays the same.
+    pub fn transform(mut self, transform: Transform) -> Self {
+        (self.width, self.height) = {
+            let (width, height) = transform.size(self.width as usize, self.height as usize);
+            (width as u16, height as u16)
+        };
+        self.tilemap = self.tilemap.transform(transform);
+        self
+    }
+}
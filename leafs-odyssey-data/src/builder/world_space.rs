@@ -0,0 +1,202 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::{data::LOTile, error::LOError};
+
+use super::{shapes::line_points, Room, TileSelection, Tilemap, World};
+
+/// Index into `rooms` and position of a tile inside that room.
+type RoomPosition = (usize, (usize, usize));
+
+/// Tiles selected by their world coordinates, which may span several rooms and floors.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct WorldSelection {
+    tiles: BTreeSet<(i16, i16, i16)>,
+}
+
+impl WorldSelection {
+    /// Empty selection.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.tiles.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.tiles.is_empty()
+    }
+    pub fn contains(&self, position: (i16, i16, i16)) -> bool {
+        self.tiles.contains(&position)
+    }
+    pub fn get_selection(&self) -> impl DoubleEndedIterator<Item = (i16, i16, i16)> + Clone + use<'_> {
+        self.tiles.iter().copied()
+    }
+
+    pub fn set(mut self, position: (i16, i16, i16), value: bool) -> Self {
+        if value {
+            self.tiles.insert(position);
+        } else {
+            self.tiles.remove(&position);
+        }
+        self
+    }
+    /// Named like [`TileSelection::add`].
+    #[allow(clippy::should_implement_trait)]
+    pub fn add(self, position: (i16, i16, i16)) -> Self {
+        self.set(position, true)
+    }
+    pub fn remove(self, position: (i16, i16, i16)) -> Self {
+        self.set(position, false)
+    }
+
+    /// `position` is the top left corner.
+    pub fn set_rect(mut self, position: (i16, i16, i16), width: u16, height: u16, value: bool) -> Self {
+        let (x, y, z) = position;
+        for y in y..y.saturating_add_unsigned(height) {
+            for x in x..x.saturating_add_unsigned(width) {
+                self = self.set((x, y, z), value);
+            }
+        }
+        self
+    }
+    pub fn add_rect(self, position: (i16, i16, i16), width: u16, height: u16) -> Self {
+        self.set_rect(position, width, height, true)
+    }
+    pub fn remove_rect(self, position: (i16, i16, i16), width: u16, height: u16) -> Self {
+        self.set_rect(position, width, height, false)
+    }
+
+    /// Line on the floor `z`, including both ends.
+    pub fn set_line(mut self, from: (i16, i16), to: (i16, i16), z: i16, value: bool) -> Self {
+        let (from, to) = ((from.0 as isize, from.1 as isize), (to.0 as isize, to.1 as isize));
+        for (x, y) in line_points(from, to) {
+            self = self.set((x as i16, y as i16, z), value);
+        }
+        self
+    }
+    pub fn add_line(self, from: (i16, i16), to: (i16, i16), z: i16) -> Self {
+        self.set_line(from, to, z, true)
+    }
+    pub fn remove_line(self, from: (i16, i16), to: (i16, i16), z: i16) -> Self {
+        self.set_line(from, to, z, false)
+    }
+}
+
+impl World {
+    /// Room of `room_width` and `room_height` on their grid which contains the tile. It is not added to the world.
+    fn grid_room(&self, position: (i16, i16, i16)) -> Result<Room, LOError> {
+        let (width, height) = (self.room_width, self.room_height);
+        if width == 0 || height == 0 {
+            return Err(LOError::InvalidRoomSize { width, height });
+        }
+        // Every tile of the room has to fit into `i16`.
+        let origin = |position: i16, size: u16| {
+            let origin = (position as i32).div_euclid(size as i32) * size as i32;
+            i16::try_from(origin).ok().filter(|_| origin + size as i32 - 1 <= i16::MAX as i32)
+        };
+        let (Some(x), Some(y)) = (origin(position.0, width), origin(position.1, height)) else {
+            return Err(LOError::InvalidWorldPosition { position });
+        };
+
+        let room = Room::new_sized((x, y, position.2), width, height);
+        if !self.rooms_overlapping(&room).is_empty() {
+            return Err(LOError::InvalidWorldPosition { position });
+        }
+        Ok(room)
+    }
+
+    /// Index into `rooms` and local position of the room containing each tile. Missing rooms are created on the grid
+    /// of `room_width` and `room_height`, but only if all of them can be.
+    pub(super) fn room_indices_at_or_create<I>(&mut self, positions: I) -> Result<Vec<RoomPosition>, LOError>
+    where
+        I: IntoIterator<Item = (i16, i16, i16)>,
+    {
+        let mut created: Vec<Room> = vec![];
+        let mut found = vec![];
+        for position in positions {
+            let existing = self
+                .rooms
+                .iter()
+                .chain(&created)
+                .enumerate()
+                .find_map(|(index, room)| Some((index, room.local_position(position)?)));
+            let entry = match existing {
+                Some(entry) => entry,
+                None => {
+                    let room = self.grid_room(position)?;
+                    let local = room.local_position(position).ok_or(LOError::InvalidWorldPosition { position })?;
+                    created.push(room);
+                    (self.rooms.len() + created.len() - 1, local)
+                }
+            };
+            found.push(entry);
+        }
+
+        for room in created {
+            self.add_room(room);
+        }
+        Ok(found)
+    }
+
+    /// Room containing the tile and the position of the tile inside it. Missing rooms are created on the grid of
+    /// `room_width` and `room_height`.
+    pub fn room_at_or_create(&mut self, position: (i16, i16, i16)) -> Result<(&mut Room, (usize, usize)), LOError> {
+        let (index, local) = self.room_indices_at_or_create([position])?[0];
+        Ok((&mut self.rooms[index], local))
+    }
+
+    /// `None` if no room contains the tile.
+    pub fn get_tile(&self, position: (i16, i16, i16), layer: u8) -> Result<Option<&LOTile>, LOError> {
+        match self.room_at(position) {
+            Some((room, (x, y))) => Ok(room.tilemap.get_layer(layer)?.get(y, x)),
+            None => Ok(None),
+        }
+    }
+
+    /// Writes the tile like [`super::Tilemap::write`], creating the room if needed.
+    pub fn set_tile(&mut self, position: (i16, i16, i16), tile: &LOTile) -> Result<(), LOError> {
+        self.write(tile, &WorldSelection::new().add(position))
+    }
+
+    /// Splits the selection into one selection per room, creating rooms if needed.
+    fn room_selections(&mut self, selection: &WorldSelection) -> Result<Vec<(usize, TileSelection)>, LOError> {
+        let mut positions: BTreeMap<usize, Vec<(usize, usize)>> = BTreeMap::new();
+        for (index, local) in self.room_indices_at_or_create(selection.get_selection())? {
+            positions.entry(index).or_default().push(local);
+        }
+        let selections = positions
+            .into_iter()
+            .map(|(index, positions)| {
+                let room_selection = self.rooms[index].tilemap.select();
+                (index, positions.into_iter().fold(room_selection, |selection, (x, y)| selection.add(x, y)))
+            })
+            .collect();
+        Ok(selections)
+    }
+
+    /// Like [`super::Tilemap::write`], across rooms. Missing rooms are created, but not if the tile can not be written.
+    pub fn write(&mut self, tile: &LOTile, selection: &WorldSelection) -> Result<(), LOError> {
+        if Tilemap::layer_of(tile).is_none() {
+            return Err(LOError::UnexpectedTile {
+                tile: tile.clone(),
+                expected: "a puzzle element",
+            });
+        }
+        for (index, room_selection) in self.room_selections(selection)? {
+            self.rooms[index].tilemap.write(tile, &room_selection)?;
+        }
+        Ok(())
+    }
+
+    /// Like [`super::Tilemap::write_on_layer`], across rooms. Missing rooms are created, but not if the layer is
+    /// invalid.
+    pub fn write_on_layer(&mut self, layer: u8, tile: &LOTile, selection: &WorldSelection) -> Result<(), LOError> {
+        if !Tilemap::LAYER_INDICES.contains(&layer) {
+            return Err(LOError::InvalidLayer { layer });
+        }
+        for (index, room_selection) in self.room_selections(selection)? {
+            self.rooms[index].tilemap.write_on_layer(layer, tile, &room_selection)?;
+        }
+        Ok(())
+    }
+}
@@ -1,47 +1,80 @@
-use std::{
-    collections::VecDeque,
-    error::Error,
-    io::{Seek, Write},
-};
-
-use binrw::{binrw, BinResult, BinWrite, NullString};
+use std::io::{Cursor, Read, Seek, SeekFrom, Write};
 
-use crate::utils::NullSink;
+use binrw::{binrw, BinRead, BinResult, BinWrite, Endian, NullString};
 
-pub static mut HACKY_LIST: Vec<u32> = vec![];
+use crate::{
+    error::{LOError, LOErrorContext},
+    guid::{AuthorGuid, WorldGuid},
+    utils::NullSink,
+};
 
 #[binrw]
 #[brw(little, magic = b"StFB")]
 pub struct LOWorld {
-    #[bw(calc = 0)] // always
-    _unknown1: u32,
+    /// 0 in worlds written by the game.
+    pub(crate) unknown1: u32,
     zone_stem_offset: u32,
     #[br(parse_with = room_count_minus_one)]
     #[bw(write_with = room_count_plus_one)]
-    room_count: u32,
+    pub(crate) room_count: u32,
     #[br(args(room_count))]
     pub zone: LOZone,
-    #[br(parse_with = binrw::helpers::until_eof)]
+    #[br(parse_with = read_stems)]
     pub stems: Vec<LOStem>,
 }
 
 #[binrw::parser(reader)]
 fn room_count_minus_one() -> BinResult<u32> {
+    let pos = reader.stream_position()?;
     let mut bytes = [0u8; 4];
     reader.read_exact(&mut bytes)?;
-    Ok(u32::from_le_bytes(bytes) - 1)
+    // The count includes the zone.
+    u32::from_le_bytes(bytes)
+        .checked_sub(1)
+        .ok_or_else(|| binrw::Error::AssertFail {
+            pos,
+            message: "Room count must be at least 1.".into(),
+        })
 }
 
 #[binrw::writer(writer)]
 fn room_count_plus_one(value: &u32) -> BinResult<()> {
     let bytes = (value + 1).to_le_bytes();
-    writer.write(&bytes)?;
+    writer.write_all(&bytes)?;
     Ok(())
 }
 
+/// Reads stems until the end of the stream, remembering which stem failed to parse.
+#[binrw::parser(reader, endian)]
+fn read_stems() -> BinResult<Vec<LOStem>> {
+    let start = reader.stream_position()?;
+    let end = reader.seek(SeekFrom::End(0))?;
+    reader.seek(SeekFrom::Start(start))?;
+
+    let mut stems: Vec<LOStem> = vec![];
+    let mut offset = start;
+    while offset < end {
+        let stem = LOStem::read_options(reader, endian, ()).map_err(|error| {
+            // Room stems follow the zone stem in the same order as its room info.
+            let room_id = stems.first().and_then(|zone| match &zone.content {
+                LOStemContent::TileZoneMap { room_info, .. } => {
+                    stems.len().checked_sub(1).and_then(|i| room_info.get(i)).map(|room| room.id)
+                }
+                _ => None,
+            });
+            let context = LOErrorContext::at_offset(offset).with_stem(stems.len(), room_id);
+            LOError::with_context(error, context)
+        })?;
+        stems.push(stem);
+        offset = reader.stream_position()?;
+    }
+    Ok(stems)
+}
+
 impl LOWorld {
     pub fn new(zone: LOZone, stems: Vec<LOStem>) -> Self {
         Self {
+            unknown1: 0,
             room_count: zone.rooms.len() as u32,
             zone_stem_offset: 0,
             zone,
@@ -49,36 +82,195 @@ impl LOWorld {
         }
     }
 
-    pub unsafe fn write_world<W: Write + Seek>(
+    /// Like [`BinRead::read`], but with an error telling which stem, room and layer could not be read.
+    pub fn read_world<R: Read + Seek>(read: &mut R) -> Result<Self, LOError> {
+        Ok(Self::read(read)?)
+    }
+
+    /// Writes the world, filling in the stem offsets and lengths of the zone and each room beforehand.
+    /// Expects the first stem to be the zone, followed by one stem per room.
+    pub fn write_world<W: Write + Seek>(
         &mut self,
         write: &mut W,
-    ) -> Result<(), Box<dyn Error>> {
-        // First pass: store stem lengths/offsets
+    ) -> Result<(), LOError> {
+        if self.stems.len() != self.zone.rooms.len() + 1 {
+            return Err(LOError::StemCount {
+                expected: self.zone.rooms.len() + 1,
+                found: self.stems.len(),
+            });
+        }
+
+        // First pass: measure each stem, as well as everything that comes before them.
+        let stem_lengths = self
+            .stems
+            .iter()
+            .enumerate()
+            .map(|(i, stem)| {
+                let mut sink = NullSink::new();
+                stem.write(&mut sink).map_err(|error| {
+                    let room_id = match &stem.content {
+                        LOStemContent::TileMapEdit { id, .. } => Some(*id),
+                        _ => None,
+                    };
+                    LOError::with_context(error, LOErrorContext::default().with_stem(i, room_id))
+                })?;
+                Ok(sink.stream_position()? as u32)
+            })
+            .collect::<Result<Vec<u32>, LOError>>()?;
+
         let mut sink = NullSink::new();
         self.write(&mut sink)?;
+        let mut stem_offset = sink.stream_position()? as u32 - stem_lengths.iter().sum::<u32>();
+
+        // Second pass: write with the computed offsets.
+        let mut locations = stem_lengths.into_iter().map(|stem_length| {
+            let location = (stem_offset, stem_length);
+            stem_offset += stem_length;
+            location
+        });
+
+        let (zone_offset, zone_length) = locations.next().unwrap();
+        self.zone_stem_offset = zone_offset;
+        self.zone.stem_offset = zone_offset;
+        self.zone.stem_length = zone_length;
+
+        for (room, (room_offset, room_length)) in self.zone.rooms.iter_mut().zip(locations) {
+            room.stem_offset = room_offset;
+            room.stem_length = room_length;
+        }
 
-        assert_eq!(HACKY_LIST.len() % 2, 0);
-
-        let mut funky_list = HACKY_LIST
-            .chunks_exact(2)
-            .flat_map(|el| [el[0] - 4, el[1] - el[0] + 4])
-            .collect::<VecDeque<u32>>();
+        self.write(write)?;
+        Ok(())
+    }
 
-        self.zone_stem_offset = funky_list.pop_front().unwrap();
-        self.zone.stem_offset = self.zone_stem_offset;
-        self.zone.stem_length = funky_list.pop_front().unwrap();
+    /// Game version whose layout every stem follows.
+    /// `None` if any stem has metadata of an unknown version, or if the stems disagree.
+    pub fn detect_version(&self) -> Option<LOFormatVersion> {
+        let mut versions = self.stems.iter().map(|stem| match &stem.content {
+            LOStemContent::TileZoneMap { metadata, .. } => LOFormatVersion::from_zone_metadata(metadata),
+            LOStemContent::TileMapEdit { metadata, .. } => LOFormatVersion::from_tile_map_metadata(metadata),
+        });
+        let version = versions.next()??;
+        versions.all(|other| other == Some(version)).then_some(version)
+    }
 
-        for room in &mut self.zone.rooms {
-            room.stem_offset = funky_list.pop_front().unwrap();
-            room.stem_length = funky_list.pop_front().unwrap();
+    /// Changes the layout of every stem to the one of the given game version, so it can be saved for that version.
+    /// Fields unknown to this crate are dropped if the version does not have them, missing fields use their defaults.
+    pub fn set_version(&mut self, version: LOFormatVersion) {
+        for stem in &mut self.stems {
+            match &mut stem.content {
+                LOStemContent::TileZoneMap { metadata, compatibility, extra_fields, .. } => {
+                    *metadata = version.zone_metadata();
+                    *compatibility = version.compatibility();
+                    extra_fields.retain(|field| metadata.fields.iter().any(|known| *known.name() == field.name));
+                },
+                LOStemContent::TileMapEdit { metadata, extra_fields, .. } => {
+                    *metadata = version.tile_map_metadata();
+                    extra_fields.retain(|field| metadata.stem_fields().iter().any(|known| *known.name() == field.name));
+                },
+            }
         }
+    }
+}
 
-        assert!(funky_list.is_empty());
+/// Everything in a world file before the stems.
+#[binrw::binread]
+#[br(little, magic = b"StFB")]
+struct LOWorldHeader {
+    unknown1: u32,
+    zone_stem_offset: u32,
+    #[br(parse_with = room_count_minus_one)]
+    room_count: u32,
+    #[br(args(room_count))]
+    zone: LOZone,
+}
 
-        self.write(write)?;
+/// Reads the header and zone table of a world up front, and individual stems only when requested,
+/// by seeking to the offsets stored in the zone table.
+pub struct LOWorldReader<R> {
+    reader: R,
+    unknown1: u32,
+    zone_stem_offset: u32,
+    zone: LOZone,
+    /// Ids from the room info of the zone stem, once it was read.
+    room_ids: Option<Vec<u32>>,
+}
 
-        HACKY_LIST.clear();
-        Ok(())
+impl<R: Read + Seek> LOWorldReader<R> {
+    pub fn new(mut reader: R) -> Result<Self, LOError> {
+        let header = LOWorldHeader::read(&mut reader)?;
+        Ok(Self {
+            reader,
+            unknown1: header.unknown1,
+            zone_stem_offset: header.zone_stem_offset,
+            zone: header.zone,
+            room_ids: None,
+        })
+    }
+
+    pub fn zone(&self) -> &LOZone {
+        &self.zone
+    }
+
+    pub fn room_count(&self) -> usize {
+        self.zone.rooms.len()
+    }
+
+    /// Reads the stem describing the world, which is always a `TileZoneMap`.
+    pub fn read_zone_stem(&mut self) -> Result<LOStem, LOError> {
+        let stem = self.read_stem(self.zone_stem_offset, 0, None)?;
+        self.room_ids = Some(match &stem.content {
+            LOStemContent::TileZoneMap { room_info, .. } => room_info.iter().map(|room| room.id).collect(),
+            _ => vec![],
+        });
+        Ok(stem)
+    }
+
+    /// Reads the stem of the room at `index` in the zone table, which follows the order of the zone's room info.
+    /// Errors only tell the id of the room if the zone stem was read before.
+    pub fn read_room_stem(&mut self, index: usize) -> Result<LOStem, LOError> {
+        let room = self
+            .zone
+            .rooms
+            .get(index)
+            .ok_or(LOError::InvalidRoomIndex { index })?;
+        let room_id = self.room_ids.as_ref().and_then(|ids| ids.get(index)).copied();
+        self.read_stem(room.stem_offset, index + 1, room_id)
+    }
+
+    /// Reads the stem of the room with the given id, looking up its index in the zone stem first. The zone stem is
+    /// only read once.
+    pub fn read_room_stem_by_id(&mut self, id: u32) -> Result<Option<LOStem>, LOError> {
+        if self.room_ids.is_none() {
+            self.read_zone_stem()?;
+        }
+        let index = self.room_ids.iter().flatten().position(|&room_id| room_id == id);
+        match index.filter(|&index| index < self.room_count()) {
+            Some(index) => {
+                let offset = self.zone.rooms[index].stem_offset;
+                self.read_stem(offset, index + 1, Some(id)).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Reads all remaining stems, resulting in the same world as [`LOWorld::read_world`].
+    pub fn into_world(mut self) -> Result<LOWorld, LOError> {
+        let mut stems = vec![self.read_zone_stem()?];
+        for index in 0..self.room_count() {
+            stems.push(self.read_room_stem(index)?);
+        }
+        let mut world = LOWorld::new(self.zone, stems);
+        world.unknown1 = self.unknown1;
+        Ok(world)
+    }
+
+    fn read_stem(&mut self, offset: u32, stem_index: usize, room_id: Option<u32>) -> Result<LOStem, LOError> {
+        self.reader.seek(SeekFrom::Start(offset as u64))?;
+        LOStem::read(&mut self.reader).map_err(|error| {
+            let context = LOErrorContext::at_offset(offset as u64).with_stem(stem_index, room_id);
+            LOError::with_context(error, context).into()
+        })
     }
 }
 
@@ -86,7 +278,7 @@ impl LOWorld {
 #[brw(little)]
 #[br(import(room_count: u32))]
 pub struct LOZone {
-    #[bw(calc = 1007)] // always
+    /// 1007 in worlds written by the game.
     _unknown1: u32,
     pub stem_offset: u32,
     pub stem_length: u32,
@@ -99,11 +291,23 @@ pub struct LOZone {
     pub rooms: Vec<LORoom>,
 }
 
+impl LOZone {
+    /// Stem offsets and lengths are filled in by [`LOWorld::write_world`].
+    pub fn new(rooms: Vec<LORoom>) -> Self {
+        Self {
+            _unknown1: 1007,
+            stem_offset: 0,
+            stem_length: 0,
+            rooms,
+        }
+    }
+}
+
 #[binrw]
 #[brw(little)]
 #[derive(Clone)]
 pub struct LORoom {
-    #[bw(calc = 1002)] // always
+    /// 1002 in worlds written by the game.
     _unknown1: u32,
     pub stem_offset: u32,
     pub stem_length: u32,
@@ -114,38 +318,81 @@ pub struct LORoom {
     _magic: Vec<u8>,
 }
 
-#[binrw::writer(writer)]
-fn remember_stream_position(_: &u32) -> BinResult<()> {
-    unsafe {
-        HACKY_LIST.push(writer.stream_position().unwrap() as u32);
+/// Stem offset and length are filled in by [`LOWorld::write_world`].
+impl Default for LORoom {
+    fn default() -> Self {
+        Self {
+            _unknown1: 1002,
+            stem_offset: 0,
+            stem_length: 0,
+        }
     }
-    Ok(())
 }
 
 #[binrw]
-#[bw(stream = stream)]
 #[brw(little, magic = b"metS")]
 pub struct LOStem {
-    #[br(ignore)]
-    #[bw(write_with = remember_stream_position)]
-    byte_offset: u32,
     pub content: LOStemContent,
-    #[br(ignore)]
-    #[bw(write_with = remember_stream_position)]
-    byte_length: u32,
 }
 
 impl LOStem {
     pub fn from_content(content: LOStemContent) -> Self {
-        Self {
-            byte_length: 0,
-            byte_offset: 0,
-            content,
+        Self { content }
+    }
+}
+
+/// Layouts of world files written by known game versions, which is only 1.0.13 so far.
+/// The layout of a stem is described by its metadata, so worlds of unknown versions can still be read and written.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LOFormatVersion {
+    V1_0_13,
+}
+
+impl LOFormatVersion {
+    pub const ALL: [Self; 1] = [Self::V1_0_13];
+    pub const LATEST: Self = Self::V1_0_13;
+
+    pub fn zone_metadata(&self) -> LOZoneMetadata {
+        let bytes = match self {
+            Self::V1_0_13 => TILE_ZONE_MAP_METADATA_1_0_13.as_slice(),
+        };
+        LOZoneMetadata::read(&mut Cursor::new(bytes)).expect("Built-in zone metadata is valid.")
+    }
+
+    pub fn tile_map_metadata(&self) -> LOTileMapMetadata {
+        let bytes = match self {
+            Self::V1_0_13 => TILE_MAP_EDIT_METADATA_1_0_13.as_slice(),
+        };
+        LOTileMapMetadata::read(&mut Cursor::new(bytes)).expect("Built-in tilemap metadata is valid.")
+    }
+
+    /// Value of the `compatibility` field of the zone.
+    pub fn compatibility(&self) -> u32 {
+        match self {
+            Self::V1_0_13 => 0,
         }
     }
+
+    /// Version which writes the given metadata, if known.
+    pub fn from_zone_metadata(metadata: &LOZoneMetadata) -> Option<Self> {
+        Self::ALL.into_iter().find(|version| version.zone_metadata() == *metadata)
+    }
+
+    /// Version which writes the given metadata, if known.
+    pub fn from_tile_map_metadata(metadata: &LOTileMapMetadata) -> Option<Self> {
+        Self::ALL.into_iter().find(|version| version.tile_map_metadata() == *metadata)
+    }
 }
 
-const TILE_ZONE_MAP_METADATA: [u8; 0xA5] = [
+impl std::fmt::Display for LOFormatVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::V1_0_13 => write!(f, "1.0.13"),
+        }
+    }
+}
+
+const TILE_ZONE_MAP_METADATA_1_0_13: [u8; 0xA5] = [
     0x01, 0x00, 0x01, 0x09, 0x00, 0x00, 0x00, 0x06, 0x00, 0x00, 0x00, 0x22, 0x00, 0x00, 0x00, 0x00,
     0x00, 0x04, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x0C, 0x00, 0x00, 0x00, 0x23, 0x00, 0x00,
     0x01, 0x00, 0x00, 0x00, 0x01, 0x0C, 0x00, 0x00, 0x00, 0x24, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00,
@@ -159,7 +406,7 @@ const TILE_ZONE_MAP_METADATA: [u8; 0xA5] = [
     0x00, 0x00, 0x00, 0x00, 0x00,
 ];
 
-const TILE_MAP_EDIT_METADATA: [u8; 0x199] = [
+const TILE_MAP_EDIT_METADATA_1_0_13: [u8; 0x199] = [
     0x01, 0x00, 0x0A, 0x00, 0x00, 0x00, 0x1C, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x05, 0x00,
     0x00, 0x00, 0x20, 0x00, 0x02, 0x00, 0x00, 0x00, 0x08, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00,
     0x02, 0x00, 0x00, 0x00, 0x2D, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x0F, 0x00, 0x00, 0x00,
@@ -194,36 +441,74 @@ const TILE_MAP_EDIT_METADATA: [u8; 0x199] = [
 pub enum LOStemContent {
     #[brw(magic = b"tile_zone_map\0")]
     TileZoneMap {
-        #[bw(calc = 0)] // always
-        _unknown1: u16,
-        #[bw(calc = 1)] // always
+        /// 0 in worlds written by the game.
+        unknown1: u16,
+        /// 1 in worlds written by the game.
         id: u32,
         /// Name of the zone / world.
         name: NullString,
-        #[bw(calc = TILE_ZONE_MAP_METADATA.to_vec())] // always
-        #[br(count = TILE_ZONE_MAP_METADATA.len(), assert(metadata == TILE_ZONE_MAP_METADATA.to_vec()))]
-        metadata: Vec<u8>,
+        /// Describes the fields after `room_info`.
+        metadata: LOZoneMetadata,
         #[bw(calc = room_info.len() as u32)]
         room_count: u32,
         #[br(count = room_count)]
         room_info: Vec<LORoomInfo>,
+        #[br(temp, parse_with = read_fields, args(&metadata.fields))]
+        #[bw(assert(
+            guid_author.segments()[1] <= u32::MAX as u64,
+            LOError::InvalidGuid { guid: guid_author.to_string() }
+        ))]
+        #[bw(calc = collect_fields(&metadata.fields, &[
+            (ZONE_FIELD_START_ROOM, LOFieldValue::Int(*start_room as i32)),
+            (ZONE_FIELD_DESCRIPTION, LOFieldValue::String(description.clone())),
+            (ZONE_FIELD_AUTHOR, LOFieldValue::String(author.clone())),
+            (ZONE_FIELD_GUID_WORLD, LOFieldValue::Long(guid_world.segments()[0])),
+            (ZONE_FIELD_GUID_AUTHOR1, LOFieldValue::Long(guid_author.segments()[0])),
+            (ZONE_FIELD_GUID_AUTHOR2, LOFieldValue::Int(guid_author.segments()[1] as i32)),
+            (ZONE_FIELD_WORLD_REVISION, LOFieldValue::Int(*world_revision as i32)),
+            (ZONE_FIELD_ROOM_COUNT, LOFieldValue::Int(listed_room_count.unwrap_or(room_info.len() as u32) as i32)),
+            (ZONE_FIELD_COMPATIBILITY, LOFieldValue::Int(*compatibility as i32)),
+        ], extra_fields))]
+        fields: Vec<LOField>,
+        #[br(calc = field_int(&metadata.fields, &fields, ZONE_FIELD_START_ROOM) as u32)]
+        #[bw(ignore)]
         start_room: u32,
+        #[br(calc = field_string(&metadata.fields, &fields, ZONE_FIELD_DESCRIPTION))]
+        #[bw(ignore)]
         description: NullString,
+        #[br(calc = field_string(&metadata.fields, &fields, ZONE_FIELD_AUTHOR))]
+        #[bw(ignore)]
         author: NullString,
-        #[brw(pad_after = 4)]
-        guid_world: u32,
-        #[brw(pad_after = 4)]
-        guid_author1: u32,
-        guid_author2: u32,
+        #[br(calc = WorldGuid::from_segments([field_int(&metadata.fields, &fields, ZONE_FIELD_GUID_WORLD) as u64]))]
+        #[bw(ignore)]
+        guid_world: WorldGuid,
+        /// The second segment is stored in a 32 bit field, writing fails if it does not fit.
+        #[br(calc = AuthorGuid::from_segments([
+            field_int(&metadata.fields, &fields, ZONE_FIELD_GUID_AUTHOR1) as u64,
+            field_int(&metadata.fields, &fields, ZONE_FIELD_GUID_AUTHOR2) as u32 as u64,
+        ]))]
+        #[bw(ignore)]
+        guid_author: AuthorGuid,
+        #[br(calc = field_int(&metadata.fields, &fields, ZONE_FIELD_WORLD_REVISION) as u32)]
+        #[bw(ignore)]
         world_revision: u32,
-        #[bw(calc = room_info.len() as u32)]
-        _room_count_again: u32,
+        /// Room count stored in the fields, which should equal the length of `room_info`. `None` if the metadata has
+        /// no such field, and to write the length of `room_info`.
+        #[br(calc = optional_field_int(&metadata.fields, &fields, ZONE_FIELD_ROOM_COUNT).map(|count| count as u32))]
+        #[bw(ignore)]
+        listed_room_count: Option<u32>,
+        #[br(calc = field_int(&metadata.fields, &fields, ZONE_FIELD_COMPATIBILITY) as u32)]
+        #[bw(ignore)]
         compatibility: u32,
+        /// Fields described by the metadata which are not known to this crate.
+        #[br(calc = unknown_fields(&fields, &ZONE_FIELDS))]
+        #[bw(ignore)]
+        extra_fields: Vec<LOField>,
     },
     #[brw(magic = b"tilemap_edit\0")]
     TileMapEdit {
-        #[bw(calc = 0)] // always
-        _unknown1: u16,
+        /// 0 in worlds written by the game.
+        unknown1: u16,
         id: u32,
         /// Name of the map / room.
         name: NullString,
@@ -231,53 +516,371 @@ pub enum LOStemContent {
         width: u16,
         /// Should be 16.
         height: u16,
-        #[bw(calc = 0)]
-        _unknown2: u32,
-        #[bw(calc = TILE_MAP_EDIT_METADATA.to_vec())]
-        #[br(count = TILE_MAP_EDIT_METADATA.len(), assert(metadata == TILE_MAP_EDIT_METADATA.to_vec()))]
-        metadata: Vec<u8>,
+        /// 0 in worlds written by the game.
+        unknown2: u32,
+        /// Describes tile payloads and the fields after `layers`.
+        metadata: LOTileMapMetadata,
         #[bw(calc = layers.len() as u32)]
         layer_count: u32,
         /// Should have 5 elements.
-        #[br(count = layer_count)]
+        #[br(parse_with = read_layers, args(layer_count, &metadata))]
         layers: Vec<LOLayer>,
+        #[br(temp, parse_with = read_fields, args(metadata.stem_fields()))]
+        #[bw(calc = collect_fields(metadata.stem_fields(), &[
+            (MAP_FIELD_MUSIC, LOFieldValue::Enum(music.clone() as i32 as u32)),
+            (MAP_FIELD_REVISION, LOFieldValue::Int(*revision as i32)),
+        ], extra_fields))]
+        fields: Vec<LOField>,
+        #[br(try_calc = LOMusic::try_from(field_int(metadata.stem_fields(), &fields, MAP_FIELD_MUSIC) as i32))]
+        #[bw(ignore)]
         music: LOMusic,
         /// Map / Room revision number
+        #[br(calc = field_int(metadata.stem_fields(), &fields, MAP_FIELD_REVISION) as u32)]
+        #[bw(ignore)]
         revision: u32,
+        /// Fields described by the metadata which are not known to this crate.
+        #[br(calc = unknown_fields(&fields, &MAP_FIELDS))]
+        #[bw(ignore)]
+        extra_fields: Vec<LOField>,
     },
 }
 
-/*
-TODO: metadata format... once this is understood, it has to somehow be used to drive the parsing of the actual stem data...
-
+/// Describes the fields which follow the room info of a `tile_zone_map` stem.
 #[binrw]
 #[brw(little)]
-pub struct LOStemMetadataChunk {
-    #[bw(calc = 1)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct LOZoneMetadata {
+    pub version: u16,
     _unknown1: u8,
-    #[bw(calc = 0)]
-    _unknown2: u8,
-    #[bw(calc = 1)] // TODO: this is for tilezonemap, but tilemapedit has 10, 0, 0, 0, ... here
-    _unknown3: u8,
-    #[bw(calc = metadata.len() as u32)]
-    metadata_count: u32,
-    #[bw(calc = 6)]
-    _unknown4: u32,
-    #[br(count = metadata_count)]
-    metadata: Vec<LOStemMetadata>,
+    #[bw(calc = fields.len() as u32)]
+    field_count: u32,
+    #[br(count = field_count)]
+    pub fields: Vec<LOMetadataField>,
+}
+
+impl Default for LOZoneMetadata {
+    /// Metadata as written by the latest known game version.
+    fn default() -> Self {
+        LOFormatVersion::LATEST.zone_metadata()
+    }
+}
+
+/// Describes the payloads of tiles, as well as the fields which follow the layers of a `tilemap_edit` stem.
+#[binrw]
+#[brw(little)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct LOTileMapMetadata {
+    pub version: u16,
+    #[bw(calc = entries.len() as u32)]
+    entry_count: u32,
+    #[br(count = entry_count)]
+    pub entries: Vec<LOMetadataEntry>,
+}
+
+impl Default for LOTileMapMetadata {
+    /// Metadata as written by the latest known game version.
+    fn default() -> Self {
+        LOFormatVersion::LATEST.tile_map_metadata()
+    }
+}
+
+impl LOTileMapMetadata {
+    /// Id of the entry describing the fields of the stem itself.
+    pub const STEM_ENTRY_ID: i32 = -1;
+
+    pub fn get_entry(&self, id: i32) -> Option<&LOMetadataEntry> {
+        self.entries.iter().find(|entry| entry.id == id)
+    }
+
+    /// Fields which follow the layers.
+    pub fn stem_fields(&self) -> &[LOMetadataField] {
+        self.get_entry(Self::STEM_ENTRY_ID)
+            .map(|entry| entry.fields.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// Fields which make up the payload of the tile with the given id.
+    pub fn tile_fields(&self, tile_id: u32) -> &[LOMetadataField] {
+        self.get_entry(tile_id as i32)
+            .map(|entry| entry.fields.as_slice())
+            .unwrap_or_default()
+    }
+}
+
+#[binrw]
+#[brw(little)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct LOMetadataEntry {
+    /// Tile id whose payload is described, or [`LOTileMapMetadata::STEM_ENTRY_ID`].
+    pub id: i32,
+    #[bw(calc = fields.len() as u32)]
+    field_count: u32,
+    #[br(count = field_count)]
+    pub fields: Vec<LOMetadataField>,
+}
+
+/// Type and name of a single field. Names are usually a single character.
+#[binrw]
+#[brw(little)]
+#[derive(Clone, Debug, PartialEq)]
+pub enum LOMetadataField {
+    /// 4 bytes, used for directions and music.
+    #[brw(magic = 0x05u32)]
+    Enum {
+        name: NullString,
+        default: u32,
+        _unknown1: [u32; 3],
+    },
+    /// 4 bytes.
+    #[brw(magic = 0x06u32)]
+    Int {
+        name: NullString,
+        default: i32,
+        _unknown1: [u32; 2],
+    },
+    /// 8 bytes, used for GUIDs.
+    #[brw(magic = 0x08u32)]
+    Long {
+        name: NullString,
+        default: u64,
+        _unknown1: [u32; 2],
+    },
+    #[brw(magic = 0x0Cu32)]
+    String {
+        name: NullString,
+        default: NullString,
+        _unknown1: u32,
+        _unknown2: u8,
+    },
+    /// A count, followed by that many elements made up of the given fields.
+    #[brw(magic = 0x0Fu32)]
+    List {
+        name: NullString,
+        element: LOMetadataEntry,
+    },
+}
+
+impl LOMetadataField {
+    pub fn name(&self) -> &NullString {
+        match self {
+            Self::Enum { name, .. }
+            | Self::Int { name, .. }
+            | Self::Long { name, .. }
+            | Self::String { name, .. }
+            | Self::List { name, .. } => name,
+        }
+    }
+
+    pub fn default_value(&self) -> LOFieldValue {
+        match self {
+            Self::Enum { default, .. } => LOFieldValue::Enum(*default),
+            Self::Int { default, .. } => LOFieldValue::Int(*default),
+            Self::Long { default, .. } => LOFieldValue::Long(*default),
+            Self::String { default, .. } => LOFieldValue::String(default.clone()),
+            Self::List { .. } => LOFieldValue::List(vec![]),
+        }
+    }
+
+    /// Converts the value to the type of this field, falling back to the default if not possible.
+    pub fn coerce(&self, value: LOFieldValue) -> LOFieldValue {
+        match (self, value.as_int(), value) {
+            (Self::Enum { .. }, Some(int), _) => LOFieldValue::Enum(int as u32),
+            (Self::Int { .. }, Some(int), _) => LOFieldValue::Int(int as i32),
+            (Self::Long { .. }, Some(int), _) => LOFieldValue::Long(int as u64),
+            (Self::String { .. }, _, value @ LOFieldValue::String(_)) => value,
+            (Self::List { .. }, _, value @ LOFieldValue::List(_)) => value,
+            _ => self.default_value(),
+        }
+    }
 }
 
+/// A field read according to its [`LOMetadataField`].
 #[binrw]
 #[brw(little)]
-pub struct LOStemMetadata {
-    id: NullString,
-    // TODO: an even number of bytes (so u16), but no count comes before it. most are 00 00, but certain others introduce at least one additional u16 (like 06)
+#[br(import_raw(field: &LOMetadataField))]
+#[derive(Clone, Debug, PartialEq)]
+pub struct LOField {
+    /// Not written, as it is part of the metadata.
+    #[br(calc = field.name().clone())]
+    #[bw(ignore)]
+    pub name: NullString,
+    #[br(args_raw = field)]
+    pub value: LOFieldValue,
+}
+
+impl LOField {
+    pub fn new(name: &[u8], value: LOFieldValue) -> Self {
+        Self {
+            name: NullString(name.to_vec()),
+            value,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum LOFieldValue {
+    Enum(u32),
+    Int(i32),
+    Long(u64),
+    String(NullString),
+    List(Vec<Vec<LOField>>),
+}
+
+impl LOFieldValue {
+    pub fn as_int(&self) -> Option<i64> {
+        match self {
+            Self::Enum(value) => Some(*value as i64),
+            Self::Int(value) => Some(*value as i64),
+            Self::Long(value) => Some(*value as i64),
+            _ => None,
+        }
+    }
+
+    pub fn as_string(&self) -> Option<&NullString> {
+        match self {
+            Self::String(value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+impl BinRead for LOFieldValue {
+    type Args<'a> = &'a LOMetadataField;
+
+    fn read_options<R: Read + Seek>(reader: &mut R, endian: Endian, field: Self::Args<'_>) -> BinResult<Self> {
+        Ok(match field {
+            LOMetadataField::Enum { .. } => Self::Enum(u32::read_options(reader, endian, ())?),
+            LOMetadataField::Int { .. } => Self::Int(i32::read_options(reader, endian, ())?),
+            LOMetadataField::Long { .. } => Self::Long(u64::read_options(reader, endian, ())?),
+            LOMetadataField::String { .. } => Self::String(NullString::read_options(reader, endian, ())?),
+            LOMetadataField::List { element, .. } => {
+                let count = u32::read_options(reader, endian, ())?;
+                let elements = (0..count)
+                    .map(|_| read_fields(reader, endian, (&element.fields,)))
+                    .collect::<BinResult<_>>()?;
+                Self::List(elements)
+            }
+        })
+    }
+}
+
+impl BinWrite for LOFieldValue {
+    type Args<'a> = ();
+
+    fn write_options<W: Write + Seek>(&self, writer: &mut W, endian: Endian, _: Self::Args<'_>) -> BinResult<()> {
+        match self {
+            Self::Enum(value) => value.write_options(writer, endian, ()),
+            Self::Int(value) => value.write_options(writer, endian, ()),
+            Self::Long(value) => value.write_options(writer, endian, ()),
+            Self::String(value) => value.write_options(writer, endian, ()),
+            Self::List(elements) => {
+                (elements.len() as u32).write_options(writer, endian, ())?;
+                elements.write_options(writer, endian, ())
+            }
+        }
+    }
+}
+
+#[binrw::parser(reader, endian)]
+fn read_fields(fields: &[LOMetadataField]) -> BinResult<Vec<LOField>> {
+    fields
+        .iter()
+        .map(|field| LOField::read_options(reader, endian, field))
+        .collect()
+}
+
+/// Value of the field with the given name, or the default value of the metadata if it was not read.
+fn find_field(metadata: &[LOMetadataField], fields: &[LOField], name: &[u8]) -> Option<LOFieldValue> {
+    fields
+        .iter()
+        .find(|field| field.name.0 == name)
+        .map(|field| field.value.clone())
+        .or_else(|| {
+            metadata
+                .iter()
+                .find(|field| field.name().0 == name)
+                .map(|field| field.default_value())
+        })
+}
+
+/// Orders the values as described by the metadata. Fields missing from `known` and `extra` use their default value.
+fn collect_fields(metadata: &[LOMetadataField], known: &[(&[u8], LOFieldValue)], extra: &[LOField]) -> Vec<LOField> {
+    metadata
+        .iter()
+        .map(|field| {
+            let name = field.name().0.as_slice();
+            let value = known
+                .iter()
+                .find(|(known_name, _)| *known_name == name)
+                .map(|(_, value)| value.clone())
+                .or_else(|| {
+                    extra
+                        .iter()
+                        .find(|extra_field| extra_field.name.0 == name)
+                        .map(|extra_field| extra_field.value.clone())
+                })
+                .map(|value| field.coerce(value))
+                .unwrap_or_else(|| field.default_value());
+            LOField {
+                name: field.name().clone(),
+                value,
+            }
+        })
+        .collect()
+}
+
+/// Fields which are not stored separately.
+fn unknown_fields(fields: &[LOField], known: &[&[u8]]) -> Vec<LOField> {
+    fields
+        .iter()
+        .filter(|field| !known.contains(&field.name.0.as_slice()))
+        .cloned()
+        .collect()
+}
+
+const ZONE_FIELD_START_ROOM: &[u8] = b"\"";
+const ZONE_FIELD_DESCRIPTION: &[u8] = b"#";
+const ZONE_FIELD_AUTHOR: &[u8] = b"$";
+const ZONE_FIELD_GUID_WORLD: &[u8] = b"2";
+const ZONE_FIELD_GUID_AUTHOR1: &[u8] = b"3";
+const ZONE_FIELD_GUID_AUTHOR2: &[u8] = b"4";
+const ZONE_FIELD_WORLD_REVISION: &[u8] = b"5";
+const ZONE_FIELD_ROOM_COUNT: &[u8] = b"6";
+const ZONE_FIELD_COMPATIBILITY: &[u8] = b"9";
+const ZONE_FIELDS: [&[u8]; 9] = [
+    ZONE_FIELD_START_ROOM,
+    ZONE_FIELD_DESCRIPTION,
+    ZONE_FIELD_AUTHOR,
+    ZONE_FIELD_GUID_WORLD,
+    ZONE_FIELD_GUID_AUTHOR1,
+    ZONE_FIELD_GUID_AUTHOR2,
+    ZONE_FIELD_WORLD_REVISION,
+    ZONE_FIELD_ROOM_COUNT,
+    ZONE_FIELD_COMPATIBILITY,
+];
+
+const MAP_FIELD_MUSIC: &[u8] = b"!";
+const MAP_FIELD_REVISION: &[u8] = b"5";
+const MAP_FIELDS: [&[u8]; 2] = [MAP_FIELD_MUSIC, MAP_FIELD_REVISION];
+
+fn field_int(metadata: &[LOMetadataField], fields: &[LOField], name: &[u8]) -> i64 {
+    optional_field_int(metadata, fields, name).unwrap_or_default()
+}
+
+/// `None` if the metadata does not describe the field.
+fn optional_field_int(metadata: &[LOMetadataField], fields: &[LOField], name: &[u8]) -> Option<i64> {
+    find_field(metadata, fields, name).and_then(|value| value.as_int())
+}
+
+fn field_string(metadata: &[LOMetadataField], fields: &[LOField], name: &[u8]) -> NullString {
+    find_field(metadata, fields, name)
+        .and_then(|value| value.as_string().cloned())
+        .unwrap_or_default()
 }
-*/
 
 #[binrw]
 #[brw(little, repr = i32)]
-#[derive(Clone)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum LOMusic {
     None = -1,
     ThrowRock = 0,
@@ -297,9 +900,35 @@ pub enum LOMusic {
     Rapture = 14,
 }
 
+impl TryFrom<i32> for LOMusic {
+    type Error = LOError;
+
+    fn try_from(value: i32) -> Result<Self, Self::Error> {
+        Ok(match value {
+            -1 => Self::None,
+            0 => Self::ThrowRock,
+            1 => Self::Outside,
+            2 => Self::Quest,
+            3 => Self::Crystal,
+            4 => Self::Peril,
+            5 => Self::Marble,
+            6 => Self::Descent,
+            7 => Self::Aqua,
+            8 => Self::Beyond,
+            9 => Self::Shards,
+            10 => Self::Superfluid,
+            11 => Self::Dust,
+            12 => Self::Dread,
+            13 => Self::Aspire,
+            14 => Self::Rapture,
+            _ => return Err(LOError::UnknownMusic { value }),
+        })
+    }
+}
+
 #[binrw]
 #[brw(little, repr = u32)]
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum LODirection {
     Up = 0,
     Right = 1,
@@ -309,9 +938,11 @@ pub enum LODirection {
 
 #[binrw]
 #[brw(little)]
-#[derive(Clone, Debug)]
+#[br(import(allow_unknown: bool, metadata: &LOTileMapMetadata))]
+#[derive(Clone, Debug, PartialEq)]
 pub enum LOTile {
     // Seems to be treated the same as magic 0x01, 0x04, 0x07, 0x63 (and possibly every invalid value above that?)
+    // These are read as `Unknown` to keep their id.
     #[brw(magic = 0x00u32)]
     None,
 
@@ -522,8 +1153,8 @@ pub enum LOTile {
         tiles_count: u32,
         #[br(count = tiles_count)]
         tiles: Vec<LOStackElement>,
-        #[bw(calc = 0)]
-        _unknown1: u32,
+        /// 0 in worlds written by the game.
+        unknown1: u32,
     },
     #[brw(magic = 0x30u32)]
     ToggleSwitch {
@@ -544,9 +1175,71 @@ pub enum LOTile {
     Slug { direction: LODirection },
     #[brw(magic = 0x2Au32)]
     FlyingSnake { direction: LODirection },
+
+    /// Tile id not known to this crate, for example from a newer game version.
+    /// The payload is kept as-is, if its layout is described by the tilemap metadata (otherwise assumed to be empty).
+    #[br(pre_assert(allow_unknown))]
+    Unknown {
+        id: u32,
+        #[br(parse_with = read_payload, args(metadata.tile_fields(id)))]
+        payload: Vec<u8>,
+    },
+}
+
+/// Reads layers, remembering which layer failed to parse.
+#[binrw::parser(reader, endian)]
+fn read_layers(count: u32, metadata: &LOTileMapMetadata) -> BinResult<Vec<LOLayer>> {
+    (0..count)
+        .map(|layer| {
+            let offset = reader.stream_position()?;
+            LOLayer::read_options(reader, endian, (metadata,)).map_err(|error| {
+                LOError::with_context(error, LOErrorContext::at_offset(offset).with_layer(layer as u8))
+            })
+        })
+        .collect()
+}
+
+#[binrw::parser(reader, endian)]
+fn read_payload(fields: &[LOMetadataField]) -> BinResult<Vec<u8>> {
+    let start = reader.stream_position()?;
+    read_fields(reader, endian, (fields,))?;
+    let length = reader.stream_position()? - start;
+    reader.seek(SeekFrom::Start(start))?;
+
+    let mut payload = vec![0; length as usize];
+    reader.read_exact(&mut payload)?;
+    Ok(payload)
+}
+
+#[binrw::parser(reader, endian)]
+fn read_tiles(count: u32, metadata: &LOTileMapMetadata) -> BinResult<Vec<LOTile>> {
+    (0..count)
+        .map(|_| LOTile::read_with_metadata(reader, endian, metadata))
+        .collect()
 }
 
 impl LOTile {
+    /// Reads a tile, falling back to [`LOTile::Unknown`] if its id is not known to this crate.
+    pub fn read_with_metadata<R: Read + Seek>(
+        reader: &mut R,
+        endian: Endian,
+        metadata: &LOTileMapMetadata,
+    ) -> BinResult<Self> {
+        let position = reader.stream_position()?;
+        match Self::read_options(reader, endian, (false, metadata)) {
+            // Only fall back if no variant accepted the id, so errors in payloads of known tiles are not hidden.
+            Err(binrw::Error::EnumErrors { variant_errors, .. })
+                if variant_errors.iter().all(|(_, error)| {
+                    matches!(error, binrw::Error::BadMagic { .. } | binrw::Error::AssertFail { .. })
+                }) =>
+            {
+                reader.seek(SeekFrom::Start(position))?;
+                Self::read_options(reader, endian, (true, metadata))
+            }
+            result => result,
+        }
+    }
+
     pub fn same_type_as(&self, other: &Self) -> bool {
         std::mem::discriminant(self) == std::mem::discriminant(other)
     }
@@ -655,15 +1348,20 @@ impl LOTile {
     }
 
     /// Multiple options, but first is the "canonical" one.
-    pub fn get_trapdoor_floors(&self) -> Vec<LOTile> {
-        match self {
+    pub fn get_trapdoor_floors(&self) -> Result<Vec<LOTile>, LOError> {
+        Ok(match self {
             LOTile::TrapdoorOverPit => vec![LOTile::Pit, LOTile::Space, LOTile::Sky, LOTile::Cloud],
             LOTile::TrapdoorOverWater => vec![LOTile::Water],
             LOTile::TrapdoorOverPacificFloor => vec![LOTile::PacificFloor],
             LOTile::TrapdoorOverHotCoals => vec![LOTile::HotCoals],
             LOTile::TrapdoorOverIce => vec![LOTile::Ice],
-            _ => panic!("Input was not a trapdoor tile."),
-        }
+            _ => {
+                return Err(LOError::UnexpectedTile {
+                    tile: self.clone(),
+                    expected: "a trapdoor",
+                })
+            }
+        })
     }
 
     /// Puzzle Elements on Layer 3
@@ -733,19 +1431,93 @@ impl LOTile {
                 | Self::FlyingSnake { .. }
         )
     }
+
+    /// Tiles which toggle the tiles they are connected to (Layer 2 and 5)
+    pub fn is_trigger(&self) -> bool {
+        matches!(
+            self,
+            Self::PressurePlate { .. } | Self::SacrificeAltar { .. } | Self::ToggleSwitch { .. }
+        )
+    }
+
+    /// Tiles which can be connected to triggers (Layer 2 and 3)
+    pub fn is_toggleable(&self) -> bool {
+        matches!(
+            self,
+            Self::ToggleFloorInitiallyClosed
+                | Self::ToggleFloorInitiallyOpen
+                | Self::ToggleDoorInitiallyClosed
+                | Self::ToggleDoorInitiallyOpen
+        )
+    }
+
+    /// Positions of the tiles this tile is wired to, including those of stacked toggle switches.
+    pub fn connections(&self) -> Vec<&LOConnection> {
+        match self {
+            Self::PressurePlate { connections }
+            | Self::SacrificeAltar { connections }
+            | Self::ToggleSwitch { connections } => connections.iter().collect(),
+            Self::Stack { tiles, .. } => tiles.iter().flat_map(|element| &element.connections).collect(),
+            _ => vec![],
+        }
+    }
+
+    pub fn connections_mut(&mut self) -> Vec<&mut LOConnection> {
+        match self {
+            Self::PressurePlate { connections }
+            | Self::SacrificeAltar { connections }
+            | Self::ToggleSwitch { connections } => connections.iter_mut().collect(),
+            Self::Stack { tiles, .. } => tiles.iter_mut().flat_map(|element| &mut element.connections).collect(),
+            _ => vec![],
+        }
+    }
+
+    /// Removes connections for which `keep` returns false, including those of stacked toggle switches.
+    pub fn retain_connections<F>(&mut self, mut keep: F)
+    where
+        F: FnMut(&LOConnection) -> bool,
+    {
+        match self {
+            Self::PressurePlate { connections }
+            | Self::SacrificeAltar { connections }
+            | Self::ToggleSwitch { connections } => connections.retain(|connection| keep(connection)),
+            Self::Stack { tiles, .. } => {
+                for element in tiles {
+                    element.connections.retain(|connection| keep(connection));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Connections of trigger tiles, which do not include those of stacked toggle switches.
+    pub fn trigger_connections_mut(&mut self) -> Option<&mut Vec<LOConnection>> {
+        match self {
+            Self::PressurePlate { connections }
+            | Self::SacrificeAltar { connections }
+            | Self::ToggleSwitch { connections } => Some(connections),
+            _ => None,
+        }
+    }
 }
 
 #[binrw]
 #[brw(little)]
-#[derive(Clone, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub struct LOConnection {
     pub x_position: u16,
     pub y_position: u16,
 }
 
+impl LOConnection {
+    pub fn new(x_position: u16, y_position: u16) -> Self {
+        Self { x_position, y_position }
+    }
+}
+
 #[binrw]
 #[brw(little)]
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct LOStackElement {
     pub tile: LOStackTile,
     pub direction: LOStackDirection,
@@ -757,9 +1529,29 @@ pub struct LOStackElement {
     connections: Vec<LOConnection>,
 }
 
+impl LOStackElement {
+    /// Connections are only stored for toggle switches.
+    pub fn new(tile: LOStackTile, direction: LOStackDirection, connections: Vec<LOConnection>) -> Self {
+        Self {
+            tile,
+            direction,
+            connections,
+        }
+    }
+
+    pub fn connections(&self) -> &[LOConnection] {
+        &self.connections
+    }
+
+    /// Only written for toggle switches.
+    pub fn connections_mut(&mut self) -> &mut Vec<LOConnection> {
+        &mut self.connections
+    }
+}
+
 #[binrw]
 #[brw(little, repr = u16)]
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum LOStackDirection {
     Up = 0,
     Right = 1,
@@ -767,9 +1559,31 @@ pub enum LOStackDirection {
     Left = 3,
 }
 
+impl From<LODirection> for LOStackDirection {
+    fn from(direction: LODirection) -> Self {
+        match direction {
+            LODirection::Up => Self::Up,
+            LODirection::Right => Self::Right,
+            LODirection::Down => Self::Down,
+            LODirection::Left => Self::Left,
+        }
+    }
+}
+
+impl From<LOStackDirection> for LODirection {
+    fn from(direction: LOStackDirection) -> Self {
+        match direction {
+            LOStackDirection::Up => Self::Up,
+            LOStackDirection::Right => Self::Right,
+            LOStackDirection::Down => Self::Down,
+            LOStackDirection::Left => Self::Left,
+        }
+    }
+}
+
 #[binrw]
 #[brw(little)]
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum LOStackTile {
     #[brw(magic = 0x00u16)]
     None,
@@ -988,26 +1802,124 @@ pub enum LOStackTile {
     },
 }
 
+impl LOStackTile {
+    /// Tiles which are documented as not officially supported in stacks.
+    pub fn is_officially_supported(&self) -> bool {
+        !matches!(
+            self,
+            Self::LadderUp
+                | Self::LadderDown
+                | Self::TrapdoorOverPit
+                | Self::TrapdoorOverWater
+                | Self::TrapdoorOverHotCoals
+                | Self::TrapdoorOverIce
+                | Self::TrapdoorOverPacificFloor
+                | Self::CrumblyWall
+                | Self::CrumblyBrickWall
+                | Self::CrumblyWoodenWall
+                | Self::CrumblyStoneBrickWall
+        )
+    }
+}
+
+/// Maps between tiles without payload, which have the same ids in `LOTile` and `LOStackTile`.
+macro_rules! stack_tile_mapping {
+    ($($tile:ident),* $(,)?) => {
+        impl TryFrom<&LOTile> for LOStackTile {
+            type Error = LOError;
+
+            /// Payloads of directions, sign texts and switch connections are dropped, as stacks store directions and
+            /// connections per element. `LOTile::Unknown` maps to `LOStackTile::Custom` if it has no payload.
+            fn try_from(tile: &LOTile) -> Result<Self, Self::Error> {
+                Ok(match tile {
+                    $(LOTile::$tile => Self::$tile,)*
+                    LOTile::StartPoint { .. } => Self::StartPoint,
+                    LOTile::Sign { .. } => Self::Sign,
+                    LOTile::Stack { tiles, .. } if tiles.is_empty() => Self::Stack,
+                    LOTile::ToggleSwitch { .. } => Self::ToggleSwitch,
+                    LOTile::BombBug { .. } => Self::BombBug,
+                    LOTile::Slug { .. } => Self::Slug,
+                    LOTile::FlyingSnake { .. } => Self::FlyingSnake,
+                    LOTile::Unknown { id, payload } if payload.is_empty() && *id <= u16::MAX as u32 => {
+                        Self::Custom { id: *id as u16 }
+                    }
+                    _ => {
+                        return Err(LOError::UnexpectedTile {
+                            tile: tile.clone(),
+                            expected: "a tile which can be stacked",
+                        })
+                    }
+                })
+            }
+        }
+
+        impl From<&LOStackElement> for LOTile {
+            /// Tiles with a direction get the one of the element, signs get an empty text.
+            fn from(element: &LOStackElement) -> Self {
+                let direction = LODirection::from(element.direction.clone());
+                match element.tile {
+                    $(LOStackTile::$tile => Self::$tile,)*
+                    LOStackTile::StartPoint => Self::StartPoint { direction },
+                    LOStackTile::Sign => Self::Sign { text: NullString::default() },
+                    LOStackTile::Stack => Self::Stack { tiles: vec![], unknown1: 0 },
+                    LOStackTile::ToggleSwitch => Self::ToggleSwitch {
+                        connections: element.connections.clone(),
+                    },
+                    LOStackTile::BombBug => Self::BombBug { direction },
+                    LOStackTile::Slug => Self::Slug { direction },
+                    LOStackTile::FlyingSnake => Self::FlyingSnake { direction },
+                    LOStackTile::Custom { id } => Self::Unknown {
+                        id: id as u32,
+                        payload: vec![],
+                    },
+                }
+            }
+        }
+    };
+}
+
+stack_tile_mapping!(
+    None, Grass, Dirt, DirtPath, Sand, Snow, OvergrownGrass, RedFlowers, YellowFlowers, DeadGrass, SnowyGrass,
+    Gravel, PineNeedles, WoodenFloor, StoneFloor, TileFloor, MarbleFloor, CobblestonePath, Water, Space, Sky, Cloud,
+    Pit, Wall, WallWithWindow, WoodenWall, WoodenWallWithWindow, BrickWall, BrickWallWithWindow, StoneBrickWall,
+    StoneBrickWallWithWindow, Cliff, RoughStone, Bush, PineTree, AutumnTree, Tree, DeadTree, Pillar, WoodenFence,
+    IronFence, Rock, Cattails, TallGrass, Curtain, Lamppost, SteppingStone, Waypoint, LadderUp, LadderDown,
+    TrapdoorOverPit, TrapdoorOverWater, TrapdoorOverHotCoals, TrapdoorOverIce, TrapdoorOverPacificFloor, GoalStar,
+    CrumblyWall, CrumblyBrickWall, CrumblyWoodenWall, CrumblyStoneBrickWall, PrimeKey, TerraKey, SkyKey, InfernalKey,
+    StarKey, PushBlock, MultiPushBlock, MonsterBlock, PrimeDoor, TerraDoor, SkyDoor, InfernalDoor, StarDoor,
+    StatueRubble, PoisonTrail, AngryEye, Statue,
+);
+
 #[binrw]
 #[brw(little)]
+#[br(import(metadata: &LOTileMapMetadata))]
 #[derive(Clone)]
 pub struct LOLayer {
-    #[bw(calc = vec![0; 5])]
-    #[br(count = 5)]
-    _unknown1: Vec<u8>,
+    _unknown1: [u8; 5],
     pub width: u16,
     pub height: u16,
-    #[bw(calc = vec![1, 1, 0, 0, 0x80, 0x3F])]
-    #[br(count = 6)]
-    _unknown2: Vec<u8>,
+    _unknown2: [u8; 6],
     #[bw(calc = (*width as u32) * (*height as u32))]
     #[br(assert(tile_count == width as u32 * height as u32))]
     tile_count: u32,
     #[bw(assert(tiles.len() as u32 == tile_count))]
-    #[br(count = tile_count)]
+    #[br(parse_with = read_tiles, args(tile_count, metadata))]
     pub tiles: Vec<LOTile>,
 }
 
+impl LOLayer {
+    /// Expects `tiles` to be `width` * `height` tiles, row by row.
+    pub fn new(width: u16, height: u16, tiles: Vec<LOTile>) -> Self {
+        Self {
+            _unknown1: [0; 5],
+            width,
+            height,
+            _unknown2: [1, 1, 0, 0, 0x80, 0x3F],
+            tiles,
+        }
+    }
+}
+
 #[binrw]
 #[brw(little)]
 #[derive(Clone)]
@@ -1022,37 +1934,21 @@ pub struct LORoomInfo {
     /// Should be 16.
     pub height: u16,
     pub z_position: i16,
-    #[bw(calc = 0)] // always
+    /// 0 in worlds written by the game.
     _unknown1: u16,
 }
 
-pub fn random_guid(segments: usize) -> String {
-    (0..segments)
-        .map(|_| random_guid_segment())
-        .collect::<Vec<_>>()
-        .join("-")
-}
-
-pub fn random_guid_segment() -> String {
-    (0..8)
-        .map(|_| {
-            const HEX_DIGITS: &str = "0123456789ABCDEF";
-            let int = rand::random::<usize>() % 16;
-            HEX_DIGITS.chars().nth(int).unwrap()
-        })
-        .collect()
-}
-
-/// Given a GUID as provided by the game UI, converts it to fields that can be put into structs.
-/// Segments are split by dashes, so each dash causes the returning Vec to return one more element.
-pub fn parse_guid(guid: &str) -> Result<Vec<u32>, String> {
-    guid.split('-')
-        .map(|segment| parse_single_guid(segment))
-        .collect()
-}
-
-/// Given a GUID segment as provided by the game UI, converts it to fields that can be put into structs.
-/// Segments are split by dashes.
-pub fn parse_single_guid(guid: &str) -> Result<u32, String> {
-    u32::from_str_radix(guid, 16).map_err(|e| format!("Invalid GUID segment '{}': {}", guid, e))
+impl LORoomInfo {
+    /// Position is in tiles.
+    pub fn new(id: u32, position: (i16, i16, i16), width: u16, height: u16) -> Self {
+        Self {
+            id,
+            x_position: position.0,
+            y_position: position.1,
+            width,
+            height,
+            z_position: position.2,
+            _unknown1: 0,
+        }
+    }
 }
@@ -0,0 +1,213 @@
+use std::{env, error::Error, fmt, io};
+
+use binrw::error::Backtrace;
+
+use crate::{data::LOTile, validate::LODiagnostic};
+
+/// Where in a world an error occurred. Fields are `None` if unknown or not applicable.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LOErrorContext {
+    /// Byte offset in the stream.
+    pub offset: Option<u64>,
+    /// Index into `LOWorld::stems`.
+    pub stem_index: Option<usize>,
+    pub room_id: Option<u32>,
+    /// Index into the layers of a room, see `Tilemap::LAYER1` and following.
+    pub layer: Option<u8>,
+}
+
+impl LOErrorContext {
+    pub fn at_offset(offset: u64) -> Self {
+        Self {
+            offset: Some(offset),
+            ..Default::default()
+        }
+    }
+
+    pub fn with_stem(mut self, stem_index: usize, room_id: Option<u32>) -> Self {
+        self.stem_index = Some(stem_index);
+        self.room_id = room_id;
+        self
+    }
+
+    pub fn with_layer(mut self, layer: u8) -> Self {
+        self.layer = Some(layer);
+        self
+    }
+
+    /// Fills in unknown fields with the ones of `other`.
+    fn or(self, other: Self) -> Self {
+        Self {
+            offset: self.offset.or(other.offset),
+            stem_index: self.stem_index.or(other.stem_index),
+            room_id: self.room_id.or(other.room_id),
+            layer: self.layer.or(other.layer),
+        }
+    }
+}
+
+impl fmt::Display for LOErrorContext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut parts = vec![];
+        if let Some(stem_index) = self.stem_index {
+            parts.push(format!("stem {}", stem_index));
+        }
+        if let Some(room_id) = self.room_id {
+            parts.push(format!("room {}", room_id));
+        }
+        if let Some(layer) = self.layer {
+            parts.push(format!("layer {}", layer));
+        }
+        if let Some(offset) = self.offset {
+            parts.push(format!("offset 0x{:X}", offset));
+        }
+        if parts.is_empty() {
+            write!(f, "unknown location")
+        } else {
+            write!(f, "{}", parts.join(", "))
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum LOError {
+    /// Reading from or writing to the underlying stream failed.
+    Io(io::Error),
+    /// Binary data could not be read or written.
+    Binary {
+        context: LOErrorContext,
+        source: binrw::Error,
+    },
+    /// A world needs one stem for the zone, followed by one stem per room.
+    StemCount { expected: usize, found: usize },
+    /// GUIDs are hexadecimal segments separated by dashes.
+    InvalidGuid { guid: String },
+    UnknownMusic { value: i32 },
+    /// The tile can not be written by the method, `expected` describes which tiles can.
+    UnexpectedTile { tile: LOTile, expected: &'static str },
+    InvalidLayer { layer: u8 },
+    /// Position `(x, y)` outside of a tilemap.
+    InvalidPosition { x: usize, y: usize },
+    /// Index into the rooms of `LOZone`.
+    InvalidRoomIndex { index: usize },
+    /// No room has this id.
+    InvalidRoomId { id: u32 },
+    /// Rooms need a width and height of at least one tile.
+    InvalidRoomSize { width: u16, height: u16 },
+    /// No room contains the world position and none can be created for it, as it would overlap another room or reach
+    /// past the range of `i16`.
+    InvalidWorldPosition { position: (i16, i16, i16) },
+    /// The world is too inconsistent to be converted.
+    InvalidWorld { diagnostic: LODiagnostic },
+    EnvironmentVariable { name: &'static str, source: env::VarError },
+    UnsupportedPlatform,
+}
+
+impl LOError {
+    /// Wraps a parsing error, so the context can be recovered once it reaches the caller.
+    pub(crate) fn with_context(error: binrw::Error, context: LOErrorContext) -> binrw::Error {
+        let pos = context.offset.unwrap_or_default();
+        binrw::Error::Custom {
+            pos,
+            err: Box::new(Self::Binary { context, source: error }),
+        }
+    }
+
+    pub fn context(&self) -> Option<&LOErrorContext> {
+        match self {
+            Self::Binary { context, .. } => Some(context),
+            _ => None,
+        }
+    }
+}
+
+/// Removes context wrappers from the error, combining them into a single context.
+fn unwrap_context(error: binrw::Error) -> (LOErrorContext, binrw::Error) {
+    match error {
+        binrw::Error::Custom { pos, err } => match err.downcast::<LOError>() {
+            Ok(inner) => match *inner {
+                LOError::Binary { context, source } => {
+                    let (inner_context, source) = unwrap_context(source);
+                    (inner_context.or(context), source)
+                }
+                other => (LOErrorContext::default(), binrw::Error::Custom { pos, err: Box::new(other) }),
+            },
+            Err(err) => (LOErrorContext::default(), binrw::Error::Custom { pos, err }),
+        },
+        binrw::Error::Backtrace(backtrace) => {
+            let Backtrace { error, frames, .. } = backtrace;
+            let (context, error) = unwrap_context(*error);
+            (context, binrw::Error::Backtrace(Backtrace::new(error, frames)))
+        }
+        // Variants which did not even match the magic are irrelevant, if only one variant remains.
+        binrw::Error::EnumErrors { pos, mut variant_errors } => {
+            let mut matched = variant_errors
+                .iter()
+                .enumerate()
+                .filter(|(_, (_, error))| !matches!(error.root_cause(), binrw::Error::BadMagic { .. }))
+                .map(|(i, _)| i);
+            match (matched.next(), matched.next()) {
+                (Some(i), None) => unwrap_context(variant_errors.swap_remove(i).1),
+                _ => (LOErrorContext::default(), binrw::Error::EnumErrors { pos, variant_errors }),
+            }
+        }
+        error => (LOErrorContext::default(), error),
+    }
+}
+
+impl From<binrw::Error> for LOError {
+    fn from(error: binrw::Error) -> Self {
+        let (context, source) = unwrap_context(error);
+        match source {
+            binrw::Error::Io(error) if context == LOErrorContext::default() => Self::Io(error),
+            source => Self::Binary { context, source },
+        }
+    }
+}
+
+impl From<io::Error> for LOError {
+    fn from(error: io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+impl fmt::Display for LOError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(error) => write!(f, "{}", error),
+            Self::Binary { context, source } => write!(f, "Invalid data at {}: {}", context, source),
+            Self::StemCount { expected, found } => write!(
+                f,
+                "Expected {} stems (zone and {} rooms), found {}.",
+                expected,
+                expected - 1,
+                found
+            ),
+            Self::InvalidGuid { guid } => write!(f, "Invalid GUID '{}'.", guid),
+            Self::UnknownMusic { value } => write!(f, "Unknown music {}.", value),
+            Self::UnexpectedTile { tile, expected } => write!(f, "Expected {}, found {:?}.", expected, tile),
+            Self::InvalidLayer { layer } => write!(f, "Layer {} out of bounds.", layer),
+            Self::InvalidPosition { x, y } => write!(f, "Position ({}, {}) out of bounds.", x, y),
+            Self::InvalidRoomIndex { index } => write!(f, "Room index {} out of bounds.", index),
+            Self::InvalidRoomId { id } => write!(f, "Room {} does not exist.", id),
+            Self::InvalidRoomSize { width, height } => write!(f, "Rooms can not be {}x{} tiles.", width, height),
+            Self::InvalidWorldPosition { position } => {
+                write!(f, "No room can be created at ({}, {}, {}).", position.0, position.1, position.2)
+            }
+            Self::InvalidWorld { diagnostic } => write!(f, "Invalid world: {}", diagnostic),
+            Self::EnvironmentVariable { name, source } => write!(f, "Environment variable {}: {}", name, source),
+            Self::UnsupportedPlatform => write!(f, "Unknown target OS, can't compute worlds folder."),
+        }
+    }
+}
+
+impl Error for LOError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Io(error) => Some(error),
+            Self::Binary { source, .. } => Some(source),
+            Self::EnvironmentVariable { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,100 @@
+use std::{fmt, str::FromStr};
+
+use rand::Rng;
+
+use crate::error::LOError;
+
+/// Identifies a world or author, shown in the game UI as segments of 8 hexadecimal digits separated by dashes.
+///
+/// Each segment is stored in its own field of the zone stem. Fields may be 64 bits wide, the game only uses
+/// the lower half though. Upper halves are kept as-is, and shown with 16 digits if not 0. The second segment of
+/// author GUIDs is stored in a 32 bit field, so it can not be parsed if larger, and worlds with a larger one can not
+/// be written.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Guid<const SEGMENTS: usize> {
+    segments: [u64; SEGMENTS],
+}
+
+/// Shown as `XXXXXXXX`.
+pub type WorldGuid = Guid<1>;
+/// Shown as `XXXXXXXX-XXXXXXXX`.
+pub type AuthorGuid = Guid<2>;
+
+impl<const SEGMENTS: usize> Guid<SEGMENTS> {
+    pub fn from_segments(segments: [u64; SEGMENTS]) -> Self {
+        Self { segments }
+    }
+
+    pub fn segments(&self) -> [u64; SEGMENTS] {
+        self.segments
+    }
+
+    /// Like the game, only fills the lower half of each segment.
+    pub fn random<R: Rng + ?Sized>(rng: &mut R) -> Self {
+        Self {
+            segments: std::array::from_fn(|_| rng.gen::<u32>() as u64),
+        }
+    }
+
+    pub fn new_random() -> Self {
+        Self::random(&mut rand::thread_rng())
+    }
+
+    /// Largest value the field of the segment at `index` can store.
+    fn segment_max(index: usize) -> u64 {
+        // The second segment of author GUIDs is stored in a 32 bit field.
+        if SEGMENTS == 2 && index == 1 {
+            u32::MAX as u64
+        } else {
+            u64::MAX
+        }
+    }
+}
+
+/// All zeroes, which the game does not generate.
+impl<const SEGMENTS: usize> Default for Guid<SEGMENTS> {
+    fn default() -> Self {
+        Self { segments: [0; SEGMENTS] }
+    }
+}
+
+impl<const SEGMENTS: usize> fmt::Display for Guid<SEGMENTS> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, segment) in self.segments.iter().enumerate() {
+            if i > 0 {
+                write!(f, "-")?;
+            }
+            if *segment > u32::MAX as u64 {
+                write!(f, "{:016X}", segment)?;
+            } else {
+                write!(f, "{:08X}", segment)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<const SEGMENTS: usize> FromStr for Guid<SEGMENTS> {
+    type Err = LOError;
+
+    /// Case insensitive. Expects exactly `SEGMENTS` segments, each fitting the field it is stored in.
+    fn from_str(guid: &str) -> Result<Self, Self::Err> {
+        let invalid = || LOError::InvalidGuid { guid: guid.into() };
+        let mut segments = [0; SEGMENTS];
+        let mut parts = guid.split('-');
+        for (i, segment) in segments.iter_mut().enumerate() {
+            let part = parts.next().ok_or_else(invalid)?;
+            if part.is_empty() || part.len() > 16 || !part.chars().all(|c| c.is_ascii_hexdigit()) {
+                return Err(invalid());
+            }
+            *segment = u64::from_str_radix(part, 16).map_err(|_| invalid())?;
+            if *segment > Self::segment_max(i) {
+                return Err(invalid());
+            }
+        }
+        match parts.next() {
+            Some(_) => Err(invalid()),
+            None => Ok(Self { segments }),
+        }
+    }
+}
@@ -1,9 +1,12 @@
-use std::{env, error::Error, fs, path::{Path, PathBuf}};
+use std::{env, fs, path::{Path, PathBuf}};
+
+use crate::error::LOError;
 
 /// Unexpanded path to worlds folder.
-pub fn get_worlds_folder() -> Result<PathBuf, Box<dyn Error>> {
+pub fn get_worlds_folder() -> Result<PathBuf, LOError> {
     let path = if cfg!(target_os = "windows") {
-        let profile = env::var("USERPROFILE")?;
+        let profile = env::var("USERPROFILE")
+            .map_err(|source| LOError::EnvironmentVariable { name: "USERPROFILE", source })?;
         Path::new(&profile)
             .join(Path::new("AppData/Roaming/leafsodyssey_worlds/"))
     } else if cfg!(target_os = "macos") {
@@ -13,7 +16,7 @@ pub fn get_worlds_folder() -> Result<PathBuf, Box<dyn Error>> {
         let path = Path::new("~/.local/share/leafsodyssey_worlds/");
         fs::canonicalize(path)?
     } else {
-        panic!("Unknown target OS, can't compute worlds folder.");
+        return Err(LOError::UnsupportedPlatform);
     };
     Ok(path)
 }
\ No newline at end of file
@@ -1,9 +1,11 @@
 #![allow(dead_code)]
-#![allow(static_mut_refs)]
 #![allow(unused_variables)]
 #![feature(cfg_match)]
 
 pub mod builder;
 pub mod data;
+pub mod error;
+pub mod guid;
 pub mod io;
+pub mod validate;
 mod utils;
\ No newline at end of file
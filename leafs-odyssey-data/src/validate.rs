@@ -0,0 +1,184 @@
+use std::fmt;
+
+use crate::data::*;
+
+/// An inconsistency which would cause the game to refuse loading the world.
+#[derive(Clone, Debug, PartialEq)]
+pub enum LODiagnostic {
+    /// The first stem has to be the zone map.
+    MissingZoneMap,
+    /// The room count of `LOWorld`, the rooms of `LOZone` and the room info of the zone map disagree.
+    RoomCountMismatch { world: u32, zone: usize, zone_map: usize },
+    /// The room count stored in the fields of the zone map disagrees with its room info.
+    ZoneMapRoomCount { found: u32, expected: usize },
+    /// Multiple rooms share the same id.
+    DuplicateRoomId { room_id: u32 },
+    /// A `LORoomInfo` has no `TileMapEdit` stem with the same id.
+    MissingRoomStem { room_id: u32 },
+    /// A stem after the zone map is no room of its room info, like a second zone map.
+    UnreferencedStem { stem_index: usize },
+    /// Rooms should have exactly 5 layers.
+    LayerCount { room_id: u32, found: usize },
+    /// A layer is not the same size as its room.
+    LayerSize { room_id: u32, layer: u8, width: u16, height: u16, expected: (u16, u16) },
+    /// A layer has a different amount of tiles than its size describes.
+    TileCount { room_id: u32, layer: u8, found: usize, expected: usize },
+    /// The `start_room` of the zone map does not exist.
+    MissingStartRoom { room_id: u32 },
+    /// Worlds need exactly one `LOTile::StartPoint`.
+    StartPointCount { found: usize },
+    /// The tile at `position` is connected to a position outside its room.
+    ConnectionOutOfBounds { room_id: u32, layer: u8, position: (u16, u16), connection: (u16, u16) },
+}
+
+impl fmt::Display for LODiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingZoneMap => write!(f, "First stem is not a zone map."),
+            Self::RoomCountMismatch { world, zone, zone_map } => write!(
+                f,
+                "Room counts disagree: {} in world, {} in zone, {} in zone map.",
+                world, zone, zone_map
+            ),
+            Self::ZoneMapRoomCount { found, expected } => {
+                write!(f, "Zone map lists {} rooms, but describes {}.", found, expected)
+            }
+            Self::DuplicateRoomId { room_id } => write!(f, "Multiple rooms have the id {}.", room_id),
+            Self::MissingRoomStem { room_id } => write!(f, "Room {} has no tilemap.", room_id),
+            Self::UnreferencedStem { stem_index } => write!(f, "Stem {} is no room of the zone map.", stem_index),
+            Self::LayerCount { room_id, found } => write!(f, "Room {} has {} layers instead of 5.", room_id, found),
+            Self::LayerSize { room_id, layer, width, height, expected } => write!(
+                f,
+                "Layer {} of room {} is {}x{} instead of {}x{}.",
+                layer, room_id, width, height, expected.0, expected.1
+            ),
+            Self::TileCount { room_id, layer, found, expected } => write!(
+                f,
+                "Layer {} of room {} has {} tiles instead of {}.",
+                layer, room_id, found, expected
+            ),
+            Self::MissingStartRoom { room_id } => write!(f, "Start room {} does not exist.", room_id),
+            Self::StartPointCount { found } => write!(f, "Found {} start points instead of 1.", found),
+            Self::ConnectionOutOfBounds { room_id, layer, position, connection } => write!(
+                f,
+                "Tile at {:?} on layer {} of room {} is connected to {:?}, which is outside the room.",
+                position, layer, room_id, connection
+            ),
+        }
+    }
+}
+
+impl LOWorld {
+    /// Checks the world for inconsistencies, returning an empty list if none were found.
+    pub fn validate(&self) -> Vec<LODiagnostic> {
+        let mut diagnostics = vec![];
+
+        let rooms: Vec<_> = self
+            .stems
+            .iter()
+            .filter_map(|stem| match &stem.content {
+                LOStemContent::TileMapEdit { id, width, height, layers, .. } => Some((*id, *width, *height, layers)),
+                _ => None,
+            })
+            .collect();
+
+        match self.stems.first().map(|stem| &stem.content) {
+            Some(LOStemContent::TileZoneMap { room_info, start_room, listed_room_count, .. }) => {
+                if self.room_count as usize != self.zone.rooms.len() || self.zone.rooms.len() != room_info.len() {
+                    diagnostics.push(LODiagnostic::RoomCountMismatch {
+                        world: self.room_count,
+                        zone: self.zone.rooms.len(),
+                        zone_map: room_info.len(),
+                    });
+                }
+                if let Some(found) = listed_room_count.filter(|&found| found as usize != room_info.len()) {
+                    diagnostics.push(LODiagnostic::ZoneMapRoomCount { found, expected: room_info.len() });
+                }
+
+                for (i, info) in room_info.iter().enumerate() {
+                    if room_info[..i].iter().any(|other| other.id == info.id) {
+                        diagnostics.push(LODiagnostic::DuplicateRoomId { room_id: info.id });
+                    }
+                    if !rooms.iter().any(|(id, ..)| *id == info.id) {
+                        diagnostics.push(LODiagnostic::MissingRoomStem { room_id: info.id });
+                    }
+                }
+
+                // Each room info uses up the first remaining stem with its id.
+                let mut unused: Vec<_> = room_info.iter().map(|info| info.id).collect();
+                for (i, stem) in self.stems.iter().enumerate().skip(1) {
+                    let index = match &stem.content {
+                        LOStemContent::TileMapEdit { id, .. } => unused.iter().position(|room_id| room_id == id),
+                        _ => None,
+                    };
+                    match index {
+                        Some(index) => {
+                            unused.swap_remove(index);
+                        }
+                        None => diagnostics.push(LODiagnostic::UnreferencedStem { stem_index: i }),
+                    }
+                }
+
+                if !room_info.iter().any(|info| info.id == *start_room) {
+                    diagnostics.push(LODiagnostic::MissingStartRoom { room_id: *start_room });
+                }
+            }
+            _ => diagnostics.push(LODiagnostic::MissingZoneMap),
+        }
+
+        let mut start_points = 0;
+        for &(room_id, width, height, layers) in &rooms {
+            if layers.len() != 5 {
+                diagnostics.push(LODiagnostic::LayerCount { room_id, found: layers.len() });
+            }
+
+            for (i, layer) in layers.iter().enumerate() {
+                if (layer.width, layer.height) != (width, height) {
+                    diagnostics.push(LODiagnostic::LayerSize {
+                        room_id,
+                        layer: i as u8,
+                        width: layer.width,
+                        height: layer.height,
+                        expected: (width, height),
+                    });
+                }
+
+                let tile_count = layer.width as usize * layer.height as usize;
+                if layer.tiles.len() != tile_count {
+                    diagnostics.push(LODiagnostic::TileCount {
+                        room_id,
+                        layer: i as u8,
+                        found: layer.tiles.len(),
+                        expected: tile_count,
+                    });
+                }
+
+                // Tiles are stored row by row.
+                let row_length = (layer.width as usize).max(1);
+                for (j, tile) in layer.tiles.iter().enumerate() {
+                    if matches!(tile, LOTile::StartPoint { .. }) {
+                        start_points += 1;
+                    }
+
+                    let position = ((j % row_length) as u16, (j / row_length) as u16);
+                    for connection in tile.connections() {
+                        if connection.x_position >= width || connection.y_position >= height {
+                            diagnostics.push(LODiagnostic::ConnectionOutOfBounds {
+                                room_id,
+                                layer: i as u8,
+                                position,
+                                connection: (connection.x_position, connection.y_position),
+                            });
+                        }
+                    }
+                }
+            }
+        }
+
+        if start_points != 1 {
+            diagnostics.push(LODiagnostic::StartPointCount { found: start_points });
+        }
+
+        diagnostics
+    }
+}
@@ -0,0 +1,859 @@
+use std::collections::HashMap;
+
+use leafs_odyssey_data::{builder::*, data::*, error::LOError, validate::LODiagnostic};
+
+fn world_with_rooms(count: i16) -> World {
+    let mut world = World::new();
+    for x in 0..count {
+        let room = world.new_room((x, 0, 0));
+        world.add_room(room);
+    }
+    world
+}
+
+fn ids(world: &World) -> Vec<u32> {
+    world.rooms.iter().map(|room| room.id).collect()
+}
+
+fn room_info_ids(world: World) -> Vec<u32> {
+    let world = LOWorld::try_from(world).unwrap();
+    let LOStemContent::TileZoneMap { room_info, .. } = &world.stems[0].content else {
+        panic!("First stem is not a zone.");
+    };
+    room_info.iter().map(|info| info.id).collect()
+}
+
+#[test]
+fn ids_stay_consistent() {
+    let mut world = World::new();
+    let first = world.new_room((0, 0, 0));
+    let second = world.new_room((1, 0, 0));
+    // Added out of order.
+    world.add_room(second);
+    world.add_room(first);
+    assert_eq!(ids(&world), vec![2, 1]);
+
+    let copy = world.duplicate_room(1, (0, 16, 0)).unwrap();
+    assert_eq!(copy, 3);
+    assert_eq!(world.room(copy).unwrap().position, (0, 16, 0));
+
+    world.remove_room(2).unwrap();
+    world.reorder_room(1, 1).unwrap();
+    assert_eq!(ids(&world), vec![3, 1]);
+    assert_eq!(room_info_ids(world), vec![3, 1]);
+}
+
+#[test]
+fn added_rooms_get_unused_ids() {
+    let mut world = world_with_rooms(2);
+    let copy = world.room(1).unwrap().clone();
+    assert_eq!(world.add_room(copy), 3);
+    assert_eq!(world.add_room(Room::new((0, 0, 0))), 4);
+    assert_eq!(world.new_room((0, 0, 0)).id, 5);
+}
+
+#[test]
+fn compaction_remaps_start_room() {
+    let mut world = world_with_rooms(4);
+    world.remove_room(1);
+    world.remove_room(3);
+    world.start_room = Some(4);
+
+    let new_ids = world.compact_room_ids();
+    assert_eq!(ids(&world), vec![1, 2]);
+    assert_eq!(new_ids[&2], 1);
+    assert_eq!(new_ids[&4], 2);
+    assert_eq!(world.start_room, Some(2));
+    assert_eq!(world.new_room((0, 0, 0)).id, 3);
+}
+
+#[test]
+fn removing_start_room_resets_it() {
+    let mut world = world_with_rooms(2);
+    world.start_room = Some(2);
+    assert!(world.remove_room(2).is_some());
+    assert!(world.remove_room(2).is_none());
+    assert_eq!(world.start_room, None);
+}
+
+#[test]
+fn warns_about_start_points() {
+    let mut world = world_with_rooms(2);
+    assert_eq!(world.warnings(), vec![LODiagnostic::StartPointCount { found: 0 }]);
+    world.start_room = Some(2);
+    assert_eq!(world.warnings(), vec![]);
+
+    let start_point = LOTile::StartPoint { direction: LODirection::Up };
+    for room in &mut world.rooms {
+        let selection = room.tilemap.select().add(1, 1);
+        room.tilemap.write(&start_point, &selection).unwrap();
+    }
+    assert_eq!(world.warnings(), vec![LODiagnostic::StartPointCount { found: 2 }]);
+    world.start_room = None;
+    let selection = world.rooms[1].tilemap.select().add(1, 1);
+    world.rooms[1].tilemap.write_on_layer(Tilemap::LAYER4, &LOTile::None, &selection).unwrap();
+    assert_eq!(world.warnings(), vec![]);
+}
+
+#[test]
+fn invalid_room_operations() {
+    let mut world = world_with_rooms(2);
+    assert!(matches!(world.duplicate_room(7, (0, 0, 0)), Err(LOError::InvalidRoomId { id: 7 })));
+    assert!(matches!(world.reorder_room(7, 0), Err(LOError::InvalidRoomId { id: 7 })));
+    assert!(matches!(world.reorder_room(1, 2), Err(LOError::InvalidRoomIndex { index: 2 })));
+
+    world.rooms[1].id = 1;
+    assert!(matches!(
+        LOWorld::try_from(world),
+        Err(LOError::InvalidWorld {
+            diagnostic: LODiagnostic::DuplicateRoomId { room_id: 1 }
+        })
+    ));
+}
+
+#[test]
+fn finds_rooms_by_tile() {
+    let mut world = world_with_rooms(2);
+    world.add_room(Room::new_sized((0, 16, 1), 12, 8));
+
+    let (room, local) = world.room_at((30, 5, 0)).unwrap();
+    assert_eq!((room.id, local), (2, (6, 5)));
+    assert_eq!(room.world_position(6, 5), Some((30, 5, 0)));
+    let far = Room::new_sized((i16::MAX - 23, 0, 0), 24, 16);
+    assert_eq!(far.world_position(23, 15), Some((i16::MAX, 15, 0)));
+    assert_eq!(far.world_position(24, 0), None);
+    let (room, local) = world.room_at((11, 23, 1)).unwrap();
+    assert_eq!((room.id, local), (3, (11, 7)));
+    assert!(world.room_at((12, 16, 1)).is_none());
+    assert!(world.room_at((-1, 0, 0)).is_none());
+}
+
+#[test]
+fn finds_neighbors_of_different_sizes() {
+    let mut world = world_with_rooms(2);
+    // Two half width rooms south of room 1, and one above both rooms.
+    world.add_room(Room::new_sized((0, 16, 0), 12, 16));
+    world.add_room(Room::new_sized((12, 16, 0), 12, 16));
+    world.add_room(Room::new_sized((12, 0, 1), 24, 16));
+
+    let neighbors = |id, direction| -> Vec<u32> {
+        world.neighbors(id, direction).unwrap().iter().map(|room| room.id).collect()
+    };
+    assert_eq!(neighbors(1, Direction::East), vec![2]);
+    assert_eq!(neighbors(2, Direction::West), vec![1]);
+    assert_eq!(neighbors(1, Direction::South), vec![3, 4]);
+    assert_eq!(neighbors(4, Direction::North), vec![1]);
+    assert_eq!(neighbors(2, Direction::South), Vec::<u32>::new());
+    assert_eq!(neighbors(1, Direction::Up), vec![5]);
+    assert_eq!(neighbors(5, Direction::Down), vec![1, 2]);
+    assert!(world.neighbors(9, Direction::Up).is_err());
+}
+
+#[test]
+fn detects_overlaps() {
+    let mut world = world_with_rooms(2);
+    assert!(world.overlapping_rooms().is_empty());
+
+    let room = Room::new_sized((20, 10, 0), 8, 8);
+    assert_eq!(world.rooms_overlapping(&room), vec![1, 2]);
+    world.add_room(room);
+    assert_eq!(world.overlapping_rooms(), vec![(1, 3), (2, 3)]);
+
+    let info = |room: &Room| LORoomInfo::new(room.id, room.position, room.width, room.height);
+    assert!(info(&world.rooms[0]).overlaps(&info(&world.rooms[2])));
+    assert!(!info(&world.rooms[0]).overlaps(&info(&world.rooms[1])));
+}
+
+#[test]
+fn writes_across_rooms() {
+    let mut world = World::new();
+    world.set_tile((-1, 0, 0), &LOTile::Water).unwrap();
+    assert_eq!(world.rooms.len(), 1);
+    assert_eq!(world.rooms[0].position, (-24, 0, 0));
+
+    // Spans four rooms around the origin.
+    let selection = WorldSelection::new().add_rect((-2, -2, 0), 4, 4);
+    assert_eq!(selection.len(), 16);
+    world.write(&LOTile::Sand, &selection).unwrap();
+    assert_eq!(world.rooms.len(), 4);
+    for position in selection.get_selection() {
+        assert!(matches!(world.get_tile(position, Tilemap::LAYER1).unwrap(), Some(LOTile::Sand)));
+    }
+    assert!(matches!(world.get_tile((2, 2, 0), Tilemap::LAYER1).unwrap(), Some(LOTile::Grass)));
+    assert!(world.get_tile((0, 0, 1), Tilemap::LAYER1).unwrap().is_none());
+    assert!(world.overlapping_rooms().is_empty());
+}
+
+#[test]
+fn selects_lines() {
+    let line = WorldSelection::new().add_line((0, 0), (4, 2), 0);
+    let tiles: Vec<_> = line.get_selection().collect();
+    assert_eq!(tiles, vec![(0, 0, 0), (1, 1, 0), (2, 1, 0), (3, 2, 0), (4, 2, 0)]);
+
+    let line = WorldSelection::new().add_line((3, 30), (3, 0), 2);
+    assert_eq!(line.len(), 31);
+    assert!(line.contains((3, 17, 2)));
+    assert!(line.remove_line((3, 0), (3, 15), 2).get_selection().all(|(_, y, _)| y > 15));
+}
+
+fn wired_tilemap() -> Tilemap {
+    let mut tilemap = Tilemap::new(24, 16);
+    let at = |x, y| tilemap.select().add(x, y);
+    let (plate, switch, door, floor) = (at(1, 1), at(2, 1), at(5, 5), at(6, 5));
+    tilemap.write(&LOTile::PressurePlate { connections: vec![] }, &plate).unwrap();
+    tilemap.write(&LOTile::ToggleSwitch { connections: vec![] }, &switch).unwrap();
+    tilemap.write(&LOTile::ToggleDoorInitiallyClosed, &door).unwrap();
+    tilemap.write(&LOTile::ToggleFloorInitiallyOpen, &floor).unwrap();
+    tilemap
+}
+
+#[test]
+fn connects_triggers_to_toggleables() {
+    let mut tilemap = wired_tilemap();
+    tilemap.connect((1, 1), (5, 5)).unwrap();
+    tilemap.connect((1, 1), (6, 5)).unwrap();
+    tilemap.connect((1, 1), (6, 5)).unwrap();
+    tilemap.connect((2, 1), (5, 5)).unwrap();
+
+    assert_eq!(tilemap.targets_of((1, 1)), vec![(5, 5), (6, 5)]);
+    let triggers: Vec<_> = tilemap
+        .triggers_of((5, 5))
+        .iter()
+        .map(|connection| (connection.layer, connection.trigger))
+        .collect();
+    assert_eq!(triggers, vec![(Tilemap::LAYER2, (1, 1)), (Tilemap::LAYER5, (2, 1))]);
+
+    // Would be (6, 5) if the position was truncated.
+    assert!(matches!(tilemap.disconnect((1, 1), (0x10006, 5)), Err(LOError::InvalidPosition { x: 0x10006, y: 5 })));
+    assert!(tilemap.disconnect((1, 1), (5, 5)).unwrap());
+    assert!(!tilemap.disconnect((1, 1), (5, 5)).unwrap());
+    assert_eq!(tilemap.targets_of((1, 1)), vec![(6, 5)]);
+}
+
+#[test]
+fn rejects_invalid_connections() {
+    let mut tilemap = wired_tilemap();
+    assert!(matches!(
+        tilemap.connect((1, 1), (7, 5)),
+        Err(LOError::UnexpectedTile { tile: LOTile::None, .. })
+    ));
+    assert!(matches!(
+        tilemap.connect((5, 5), (6, 5)),
+        Err(LOError::UnexpectedTile { tile: LOTile::None, .. })
+    ));
+    assert!(matches!(tilemap.connect((1, 1), (24, 0)), Err(LOError::InvalidPosition { x: 24, y: 0 })));
+
+    tilemap.connect((1, 1), (5, 5)).unwrap();
+    tilemap.write(&LOTile::Grass, &tilemap.select().add(5, 5)).unwrap();
+    tilemap.write_on_layer(Tilemap::LAYER3, &LOTile::None, &tilemap.select().add(5, 5)).unwrap();
+    assert_eq!(tilemap.invalid_connections().len(), 1);
+    assert_eq!(tilemap.remove_invalid_connections(), 1);
+    assert!(tilemap.connections().is_empty());
+}
+
+#[test]
+fn toggleables_need_their_layer() {
+    let mut tilemap = wired_tilemap();
+    assert!(tilemap.is_toggleable_at((5, 5)) && tilemap.is_toggleable_at((6, 5)));
+    let (door, floor) = (tilemap.select().add(7, 5), tilemap.select().add(8, 5));
+    tilemap.write_on_layer(Tilemap::LAYER2, &LOTile::ToggleDoorInitiallyOpen, &door).unwrap();
+    tilemap.write_on_layer(Tilemap::LAYER3, &LOTile::ToggleFloorInitiallyOpen, &floor).unwrap();
+    assert!(!tilemap.is_toggleable_at((7, 5)) && !tilemap.is_toggleable_at((8, 5)));
+    assert!(matches!(
+        tilemap.connect((1, 1), (7, 5)),
+        Err(LOError::UnexpectedTile { tile: LOTile::ToggleDoorInitiallyOpen, .. })
+    ));
+}
+
+#[test]
+fn wires_stacked_toggle_switches() {
+    let mut tilemap = wired_tilemap();
+    let stack = StackBuilder::new()
+        .push(&LOTile::ToggleSwitch { connections: vec![] }, LOStackDirection::Up)
+        .unwrap()
+        .push(&LOTile::ToggleSwitch { connections: vec![] }, LOStackDirection::Up)
+        .unwrap()
+        .connect((7, 5))
+        .unwrap();
+    tilemap.write(&stack.build(), &tilemap.select().add(3, 1)).unwrap();
+
+    // The first switch is wired, both are disconnected.
+    tilemap.connect((3, 1), (5, 5)).unwrap();
+    tilemap.connect((3, 1), (7, 5)).unwrap_err();
+    let LOTile::Stack { tiles, .. } = &tilemap.get_layer(Tilemap::LAYER5).unwrap()[(1, 3)] else {
+        panic!("No stack at (3, 1).");
+    };
+    assert_eq!(tiles[0].connections(), &[LOConnection::new(5, 5)]);
+    assert_eq!(tilemap.targets_of((3, 1)), vec![(5, 5), (7, 5)]);
+    assert_eq!(tilemap.invalid_connections().len(), 1);
+
+    tilemap.connect((3, 1), (6, 5)).unwrap();
+    tilemap.write_on_layer(Tilemap::LAYER2, &LOTile::None, &tilemap.select().add(6, 5)).unwrap();
+    assert_eq!(tilemap.remove_invalid_connections(), 2);
+    assert_eq!(tilemap.targets_of((3, 1)), vec![(5, 5)]);
+    assert!(tilemap.disconnect((3, 1), (5, 5)).unwrap());
+    assert!(tilemap.connections().is_empty());
+    assert!(matches!(
+        tilemap.disconnect((4, 1), (5, 5)),
+        Err(LOError::UnexpectedTile { tile: LOTile::None, .. })
+    ));
+}
+
+#[test]
+fn moving_tiles_keeps_connections() {
+    let mut tilemap = wired_tilemap();
+    tilemap.connect((1, 1), (5, 5)).unwrap();
+    tilemap.connect((2, 1), (5, 5)).unwrap();
+
+    tilemap.move_tile(Tilemap::LAYER3, (5, 5), (10, 3)).unwrap();
+    assert!(tilemap.triggers_of((5, 5)).is_empty());
+    assert_eq!(tilemap.triggers_of((10, 3)).len(), 2);
+
+    tilemap.move_tile(Tilemap::LAYER2, (1, 1), (0, 0)).unwrap();
+    assert_eq!(tilemap.targets_of((0, 0)), vec![(10, 3)]);
+    assert!(tilemap.invalid_connections().is_empty());
+}
+
+#[test]
+fn builds_stacks() {
+    let stack = StackBuilder::new()
+        .push(&LOTile::PushBlock, LOStackDirection::Up)
+        .unwrap()
+        .push(&LOTile::ToggleSwitch { connections: vec![] }, LOStackDirection::Left)
+        .unwrap()
+        .connect((3, 4))
+        .unwrap()
+        .connect((3, 4))
+        .unwrap()
+        .push(&LOTile::LadderUp, LOStackDirection::Up)
+        .unwrap();
+    assert_eq!(stack.elements()[1].connections(), &[LOConnection::new(3, 4)]);
+    // Only the ladder is not officially supported.
+    assert_eq!(stack.warnings(), &["Stacked LadderUp is not officially supported!".to_string()]);
+    assert!(matches!(
+        stack.clone().connect((0, 0)),
+        Err(LOError::UnexpectedTile { tile: LOTile::LadderUp, .. })
+    ));
+    assert!(StackBuilder::new().push(&LOTile::Ice, LOStackDirection::Up).is_err());
+    let switch = StackBuilder::new().push(&LOTile::ToggleSwitch { connections: vec![] }, LOStackDirection::Up).unwrap();
+    assert!(matches!(switch.connect((3, 0x10000)), Err(LOError::InvalidPosition { x: 3, y: 0x10000 })));
+
+    let mut tilemap = Tilemap::new(24, 16);
+    tilemap.write(&stack.build(), &tilemap.select().add(1, 1)).unwrap();
+    let tiles: Vec<_> = tilemap.connections().iter().map(|connection| connection.target).collect();
+    assert_eq!(tiles, vec![(3, 4)]);
+}
+
+#[test]
+fn rotates_tiles_directions_and_connections() {
+    let mut tilemap = wired_tilemap();
+    tilemap.connect((1, 1), (5, 5)).unwrap();
+    tilemap.write(&LOTile::StartPoint { direction: LODirection::Up }, &tilemap.select().add(1, 2)).unwrap();
+    let stack = StackBuilder::new()
+        .push(&LOTile::PushBlock, LOStackDirection::Left)
+        .unwrap()
+        .build();
+    tilemap.write(&stack, &tilemap.select().add(0, 15)).unwrap();
+
+    let rotated = tilemap.clone().transform(Transform::Rotate90);
+    assert_eq!((rotated.get_width(), rotated.get_height()), (16, 24));
+    assert!(matches!(
+        rotated.layers.3.get(1, 13),
+        Some(LOTile::StartPoint { direction: LODirection::Right })
+    ));
+    assert!(matches!(rotated.layers.2.get(5, 10), Some(LOTile::ToggleDoorInitiallyClosed)));
+    assert_eq!(rotated.targets_of((14, 1)), vec![(10, 5)]);
+    let Some(LOTile::Stack { tiles, .. }) = rotated.layers.4.get(0, 0) else {
+        panic!("Stack was not moved to the top left corner.");
+    };
+    assert_eq!(tiles[0].direction, LOStackDirection::Up);
+
+    let four_times = (0..4).fold(tilemap.clone(), |tilemap, _| tilemap.transform(Transform::Rotate90));
+    assert_eq!(four_times.layers, tilemap.layers);
+    let back = rotated.transform(Transform::Rotate270);
+    assert_eq!(back.layers, tilemap.layers);
+    let half = tilemap.clone().transform(Transform::Rotate180);
+    let flipped = tilemap.clone().transform(Transform::FlipHorizontal).transform(Transform::FlipVertical);
+    assert_eq!(half.layers, flipped.layers);
+}
+
+#[test]
+fn flips_directions() {
+    let mut tilemap = Tilemap::new(24, 16);
+    tilemap.write(&LOTile::Slug { direction: LODirection::Left }, &tilemap.select().add(0, 0)).unwrap();
+    tilemap.write(&LOTile::BombBug { direction: LODirection::Up }, &tilemap.select().add(1, 0)).unwrap();
+
+    let flipped = tilemap.transform(Transform::FlipHorizontal);
+    assert!(matches!(flipped.layers.4.get(0, 23), Some(LOTile::Slug { direction: LODirection::Right })));
+    assert!(matches!(flipped.layers.4.get(0, 22), Some(LOTile::BombBug { direction: LODirection::Up })));
+    let flipped = flipped.transform(Transform::FlipVertical);
+    assert!(matches!(flipped.layers.4.get(15, 22), Some(LOTile::BombBug { direction: LODirection::Down })));
+
+    let room = Room::new_sized((24, 0, 0), 24, 16).transform(Transform::Rotate270);
+    assert_eq!((room.position, room.width, room.height), ((24, 0, 0), 16, 24));
+}
+
+#[test]
+fn stamps_keep_wiring() {
+    let mut tilemap = wired_tilemap();
+    tilemap.connect((1, 1), (5, 5)).unwrap();
+    tilemap.connect((1, 1), (6, 5)).unwrap();
+
+    let stamp = tilemap.copy_rect(1, 1, 5, 5);
+    assert_eq!((stamp.get_width(), stamp.get_height()), (5, 5));
+    let external: Vec<_> = stamp.external_connections().iter().map(|connection| connection.target).collect();
+    assert_eq!(external, vec![(6, 5)]);
+    assert!(matches!(stamp.get_tile(Tilemap::LAYER3, 4, 4), Some(LOTile::ToggleDoorInitiallyClosed)));
+
+    let mut target = Tilemap::new(24, 16);
+    target.paste(&stamp, (10, 8)).unwrap();
+    assert_eq!(target.targets_of((10, 8)), vec![(14, 12)]);
+    assert!(target.invalid_connections().is_empty());
+    assert!(matches!(target.paste(&stamp, (20, 0)), Err(LOError::InvalidPosition { x: 24, y: 4 })));
+}
+
+#[test]
+fn stamps_only_replace_selected_tiles() {
+    let mut tilemap = Tilemap::new(24, 16);
+    tilemap.write(&LOTile::Water, &tilemap.select().add(0, 0).add(2, 2)).unwrap();
+    let stamp = tilemap.copy(&tilemap.select().add(0, 0).add(2, 2));
+    assert_eq!((stamp.get_width(), stamp.get_height()), (3, 3));
+    assert!(stamp.get_tile(Tilemap::LAYER1, 1, 1).is_none());
+
+    let mut target = Tilemap::new(24, 16);
+    target.write(&LOTile::Sand, &target.select_all()).unwrap();
+    target.paste(&stamp, (4, 4)).unwrap();
+    assert!(matches!(target.layers.0.get(4, 4), Some(LOTile::Water)));
+    assert!(matches!(target.layers.0.get(5, 5), Some(LOTile::Sand)));
+    assert!(matches!(target.layers.0.get(6, 6), Some(LOTile::Water)));
+}
+
+#[test]
+fn creates_rooms_only_where_they_fit() {
+    let mut world = World::new();
+    world.add_room(Room::new_sized((20, 0, 0), 8, 8));
+    // The grid room at the origin would overlap the existing room, so nothing is created.
+    let selection = WorldSelection::new().add((-1, 0, 0)).add((0, 0, 0));
+    assert!(matches!(
+        world.write(&LOTile::Sand, &selection),
+        Err(LOError::InvalidWorldPosition { position: (0, 0, 0) })
+    ));
+    assert_eq!(world.rooms.len(), 1);
+    world.set_tile((21, 1, 0), &LOTile::Sand).unwrap();
+    assert_eq!(world.rooms.len(), 1);
+
+    // Rooms near the end of the coordinates would not fit.
+    assert!(matches!(world.set_tile((i16::MAX, 0, 0), &LOTile::Sand), Err(LOError::InvalidWorldPosition { .. })));
+    assert!(matches!(world.set_tile((i16::MIN, 0, 0), &LOTile::Sand), Err(LOError::InvalidWorldPosition { .. })));
+    world.set_tile((-32760, 0, 0), &LOTile::Sand).unwrap();
+    assert_eq!(world.rooms[1].position, (-32760, 0, 0));
+    let stamp = world.rooms[1].tilemap.copy_rect(0, 0, 2, 2);
+    assert!(matches!(world.paste(&stamp, (i16::MAX, 0, 0)), Err(LOError::InvalidWorldPosition { .. })));
+
+    // Writes which fail for every room do not create any.
+    assert_eq!(Tilemap::layer_of(&LOTile::None), None);
+    assert!(matches!(world.set_tile((0, 50, 0), &LOTile::None), Err(LOError::UnexpectedTile { .. })));
+    let selection = WorldSelection::new().add((0, 50, 0));
+    assert!(matches!(world.write_on_layer(5, &LOTile::Sand, &selection), Err(LOError::InvalidLayer { layer: 5 })));
+    assert_eq!(world.rooms.len(), 2);
+
+    world.room_width = 0;
+    assert!(matches!(world.room_at_or_create((0, 50, 0)), Err(LOError::InvalidRoomSize { width: 0, height: 16 })));
+    assert_eq!(world.rooms.len(), 2);
+}
+
+#[test]
+fn stamps_paste_across_rooms() {
+    let mut tilemap = wired_tilemap();
+    tilemap.connect((1, 1), (5, 5)).unwrap();
+    tilemap.connect((2, 1), (5, 5)).unwrap();
+    let stamp = tilemap.copy_rect(0, 0, 6, 6);
+
+    let mut world = World::new();
+    let dropped = world.paste(&stamp, (22, 0, 0)).unwrap();
+    assert_eq!(world.rooms.len(), 2);
+    // The plate stays in the first room, the switch and door end up in the second.
+    let dropped: Vec<_> = dropped.iter().map(|connection| (connection.trigger, connection.target)).collect();
+    assert_eq!(dropped, vec![((1, 1), (5, 5))]);
+    assert_eq!(world.room(2).unwrap().tilemap.targets_of((0, 1)), vec![(3, 5)]);
+}
+
+fn tile_at(world: &World, position: (i16, i16, i16)) -> Option<LOTile> {
+    world.get_tile(position, Tilemap::LAYER1).unwrap().cloned()
+}
+
+#[test]
+fn undoes_and_redoes_transactions() {
+    let mut world = world_with_rooms(2);
+    world.enable_journal();
+    world.set_tile((0, 0, 0), &LOTile::Water).unwrap();
+    world.transaction(|world| {
+        world.write(&LOTile::Sand, &WorldSelection::new().add_line((22, 0), (25, 0), 0)).unwrap();
+        world.set_tile((0, 0, 0), &LOTile::Sand).unwrap();
+    });
+    // Writing the same tile again is not an edit.
+    world.set_tile((0, 0, 0), &LOTile::Sand).unwrap();
+    world.commit();
+    assert_eq!(world.journal().unwrap().transactions().len(), 2);
+    assert_eq!(world.journal().unwrap().transactions()[1].edits.len(), 5);
+
+    assert!(world.undo().unwrap());
+    assert_eq!(tile_at(&world, (0, 0, 0)), Some(LOTile::Water));
+    assert_eq!(tile_at(&world, (25, 0, 0)), Some(LOTile::Grass));
+    assert!(world.undo().unwrap());
+    assert_eq!(tile_at(&world, (0, 0, 0)), Some(LOTile::Grass));
+    assert!(!world.undo().unwrap());
+
+    assert!(world.redo().unwrap());
+    assert_eq!(tile_at(&world, (0, 0, 0)), Some(LOTile::Water));
+    // A new edit discards the undone transaction.
+    world.set_tile((1, 0, 0), &LOTile::Water).unwrap();
+    assert!(!world.redo().unwrap());
+    assert_eq!(world.disable_journal().unwrap().transactions().len(), 2);
+}
+
+#[test]
+fn failed_undos_and_replays_change_nothing() {
+    let mut world = world_with_rooms(2);
+    world.enable_journal();
+    world.write(&LOTile::Sand, &WorldSelection::new().add_line((22, 0), (25, 0), 0)).unwrap();
+    world.commit();
+    let journal = world.journal().unwrap().clone();
+
+    // Rooms removed from `rooms` directly are still part of the journal.
+    let room = world.rooms.remove(0);
+    assert!(matches!(world.undo(), Err(LOError::InvalidRoomId { id: 1 })));
+    assert_eq!(tile_at(&world, (25, 0, 0)), Some(LOTile::Sand));
+    world.rooms.insert(0, room);
+    assert!(world.undo().unwrap());
+    assert_eq!(tile_at(&world, (22, 0, 0)), Some(LOTile::Grass));
+    assert_eq!(tile_at(&world, (25, 0, 0)), Some(LOTile::Grass));
+
+    let mut copy = world_with_rooms(1);
+    assert!(matches!(copy.replay(&journal), Err(LOError::InvalidRoomId { id: 2 })));
+    assert_eq!(tile_at(&copy, (22, 0, 0)), Some(LOTile::Grass));
+}
+
+#[test]
+fn journals_follow_removed_and_compacted_rooms() {
+    let mut world = world_with_rooms(3);
+    world.enable_journal();
+    world.set_tile((0, 0, 0), &LOTile::Water).unwrap();
+    world.commit();
+    world.write(&LOTile::Sand, &WorldSelection::new().add_line((22, 0), (25, 0), 0)).unwrap();
+    world.commit();
+    world.set_tile((50, 0, 0), &LOTile::Water).unwrap();
+    world.commit();
+    assert!(world.undo().unwrap());
+
+    // The first transaction only had edits in room 1, the undone one only in room 3.
+    world.remove_room(1).unwrap();
+    let journal = world.journal().unwrap();
+    assert_eq!(journal.transactions().len(), 1);
+    assert_eq!(journal.transactions()[0].edits.iter().map(|(id, _)| *id).collect::<Vec<_>>(), vec![2, 2]);
+    assert!(journal.can_redo());
+
+    assert_eq!(world.compact_room_ids(), HashMap::from([(2, 1), (3, 2)]));
+    assert!(world.redo().unwrap());
+    assert_eq!(tile_at(&world, (50, 0, 0)), Some(LOTile::Water));
+    assert!(world.undo().unwrap());
+    assert!(world.undo().unwrap());
+    assert_eq!(tile_at(&world, (24, 0, 0)), Some(LOTile::Grass));
+    assert!(!world.undo().unwrap());
+}
+
+#[test]
+fn replays_journals_onto_other_worlds() {
+    let mut world = world_with_rooms(1);
+    let mut copy = world_with_rooms(1);
+    world.rooms[0].tilemap = wired_tilemap();
+    copy.rooms[0].tilemap = wired_tilemap();
+
+    world.enable_journal();
+    world.set_tile((30, 0, 0), &LOTile::Water).unwrap();
+    world.commit();
+    let tilemap = &mut world.room_mut(1).unwrap().tilemap;
+    tilemap.connect((1, 1), (5, 5)).unwrap();
+    tilemap.move_tile(Tilemap::LAYER3, (5, 5), (7, 7)).unwrap();
+    let journal = world.disable_journal().unwrap();
+    assert_eq!(journal.transactions().len(), 2);
+
+    // The room created by the first transaction is missing.
+    assert!(matches!(copy.replay(&journal), Err(LOError::InvalidRoomId { id: 2 })));
+    let room = copy.new_room((1, 0, 0));
+    copy.add_room(room);
+    copy.replay(&journal).unwrap();
+    assert_eq!(tile_at(&copy, (30, 0, 0)), Some(LOTile::Water));
+    assert_eq!(copy.room(1).unwrap().tilemap.targets_of((1, 1)), vec![(7, 7)]);
+    assert_eq!(copy.room(1).unwrap().tilemap.layers, world.room(1).unwrap().tilemap.layers);
+}
+
+fn selected(selection: &TileSelection) -> Vec<(usize, usize)> {
+    selection.get_selection().collect()
+}
+
+#[test]
+fn selections_switch_backends() {
+    let selection = TileSelection::new(24, 16);
+    assert_eq!(selection.backend(), SelectionBackend::Sparse);
+    let selection = selection.add(3, 2).add(1, 5);
+    assert_eq!(selection.backend(), SelectionBackend::Sparse);
+    assert_eq!(selected(&selection), vec![(2, 3), (5, 1)]);
+    assert_eq!(selected(&selection.clone().add_rect(0, 0, 4, 4)).len(), 17);
+    assert_eq!(selection.clone().add_rect(0, 0, 4, 4).backend(), SelectionBackend::Bits);
+
+    let rect = TileSelection::new(24, 16).add_rect(20, 14, 10, 10);
+    assert_eq!(rect.backend(), SelectionBackend::Rect);
+    assert_eq!(selected(&rect), vec![(14, 20), (14, 21), (14, 22), (14, 23), (15, 20), (15, 21), (15, 22), (15, 23)]);
+    assert_eq!(rect.get_selection().next_back(), Some((15, 23)));
+    assert_eq!(rect.clone().remove(20, 14).backend(), SelectionBackend::Bits);
+    assert_eq!(TileSelection::new(24, 16).add_rect(2, 3, 2, 2).remove(2, 3).backend(), SelectionBackend::Sparse);
+    assert_eq!(rect.remove_rect(0, 0, 24, 16).backend(), SelectionBackend::Sparse);
+
+    // Bulk edits pick the smallest backend for their result.
+    let full = TileSelection::new(24, 16).add_all();
+    assert_eq!(full.backend(), SelectionBackend::Rect);
+    assert_eq!(full.clone().invert_all().backend(), SelectionBackend::Sparse);
+    let checkered = full.predicate_and(|x, y| (x + y) % 2 == 0);
+    assert_eq!(checkered.backend(), SelectionBackend::Bits);
+    assert_eq!(selected(&checkered).len(), 192);
+    assert_eq!(checkered.get_selection().next_back(), Some((15, 23)));
+    let inverted = checkered.clone().invert_all();
+    assert!(inverted.contains(1, 0) && !inverted.contains(0, 0));
+    assert_eq!(selected(&inverted.invert_all()), selected(&checkered));
+
+    for backend in [SelectionBackend::Bits, SelectionBackend::Sparse, SelectionBackend::Rect] {
+        let converted = checkered.clone().with_backend(backend);
+        assert_eq!(selected(&converted), selected(&checkered));
+    }
+    let single = TileSelection::from((2, 3)).with_backend(SelectionBackend::Bits);
+    assert_eq!(single.with_backend(SelectionBackend::Rect).backend(), SelectionBackend::Rect);
+    assert_eq!(selected(&TileSelection::from((2, 3))), vec![(3, 2)]);
+
+    // Filtering only visits selected tiles and keeps small selections small.
+    let visited = std::cell::Cell::new(0);
+    let count = |_, _| {
+        visited.set(visited.get() + 1);
+        true
+    };
+    let rect = TileSelection::new(24, 16).add_rect(2, 3, 2, 2);
+    assert_eq!(rect.predicate_and(count).backend(), SelectionBackend::Rect);
+    let sparse = TileSelection::new(24, 16).add(3, 2).add(1, 5);
+    assert_eq!(sparse.clone().predicate_and(count).backend(), SelectionBackend::Sparse);
+    assert_eq!(visited.get(), 6);
+    assert_eq!(selected(&sparse.predicate_and(|x, _| x == 1)), vec![(5, 1)]);
+    let single = TileSelection::new(24, 16).add_rect(2, 3, 2, 1).predicate_and(|x, _| x == 3);
+    assert_eq!((single.backend(), selected(&single)), (SelectionBackend::Rect, vec![(3, 3)]));
+}
+
+#[test]
+fn combines_selections() {
+    let walkable = TileSelection::new(24, 16).add_rect(0, 0, 12, 16);
+    let path = TileSelection::new(24, 16).add_rect(0, 5, 24, 2);
+    let mask = walkable.clone().difference(&path, (0, 0));
+    assert_eq!(mask.len(), 12 * 14);
+    assert!(mask.contains(3, 3) && !mask.contains(3, 5) && !mask.contains(13, 3));
+    assert_eq!(walkable.clone().intersection(&path, (0, 0)).bounding_box(), Some((0, 5, 12, 2)));
+    assert_eq!(walkable.clone().union(&path, (0, 0)).len(), 12 * 16 + 12 * 2);
+    assert_eq!(walkable.clone().symmetric_difference(&path, (0, 0)).len(), 12 * 14 + 12 * 2);
+    assert!(walkable.clone().difference(&walkable, (0, 0)).is_empty());
+
+    // Checkerboards are bitsets, which are combined per word.
+    let checkered = TileSelection::all(24, 16).predicate_and(|x, y| (x + y) % 2 == 0);
+    let odd = TileSelection::all(24, 16).difference(&checkered, (0, 0));
+    assert_eq!(odd.len(), 192);
+    assert!(odd.clone().intersection(&checkered, (0, 0)).is_empty());
+    assert_eq!(odd.union(&checkered, (0, 0)).backend(), SelectionBackend::Rect);
+
+    // Smaller selections are placed at the offset, tiles outside are ignored.
+    let brush = TileSelection::all(3, 3).remove(1, 1);
+    let stamped = TileSelection::new(24, 16).union(&brush, (-1, 14));
+    assert_eq!(selected(&stamped), vec![(14, 0), (14, 1), (15, 1)]);
+    assert_eq!(stamped.bounding_box(), Some((0, 14, 2, 2)));
+    let holes = TileSelection::all(24, 16).difference(&brush, (10, 10));
+    assert_eq!(holes.len(), 24 * 16 - 8);
+    assert!(holes.contains(11, 11) && !holes.contains(10, 10));
+    assert!(TileSelection::new(24, 16).bounding_box().is_none());
+}
+
+fn render(selection: &TileSelection) -> Vec<String> {
+    (0..selection.get_height())
+        .map(|y| (0..selection.get_width()).map(|x| if selection.contains(x, y) { '#' } else { '.' }).collect())
+        .collect()
+}
+
+#[test]
+fn draws_shapes() {
+    let selection = TileSelection::new(7, 7);
+    assert_eq!(
+        render(&selection.clone().add_circle((3, 3), 3)),
+        ["..###..", ".#####.", "#######", "#######", "#######", ".#####.", "..###.."]
+    );
+    assert_eq!(
+        render(&selection.clone().add_circle_outline((3, 3), 3)),
+        ["..###..", ".#...#.", "#.....#", "#.....#", "#.....#", ".#...#.", "..###.."]
+    );
+    assert_eq!(
+        render(&selection.clone().add_ellipse((3, 3), 3, 1)),
+        [".......", ".......", ".#####.", "#######", ".#####.", ".......", "......."]
+    );
+    assert_eq!(
+        render(&selection.clone().add_diamond((3, 3), 2)),
+        [".......", "...#...", "..###..", ".#####.", "..###..", "...#...", "......."]
+    );
+    assert_eq!(
+        render(&selection.clone().add_rect_border(0, 0, 7, 6, 2)),
+        ["#######", "#######", "##...##", "##...##", "#######", "#######", "......."]
+    );
+    assert_eq!(
+        render(&selection.clone().add_polygon(&[(0, 0), (6, 0), (0, 6)])),
+        ["#######", "######.", "#####..", "####...", "###....", "##.....", "#......"]
+    );
+    assert_eq!(
+        render(&selection.clone().add_polygon_outline(&[(1, 1), (5, 1), (5, 5), (1, 5)])),
+        render(&selection.clone().add_rect_border(1, 1, 5, 5, 1))
+    );
+
+    // Shapes are clipped to the area.
+    let corner = selection.clone().add_circle((0, 0), 2).remove_line((0, 0), (6, 6));
+    assert_eq!(render(&corner)[..3], [".##....", "#.#....", "##....."]);
+    assert_eq!(selection.add_line((6, 0), (0, 2)).len(), 7);
+}
+
+#[test]
+fn selects_by_content() {
+    let mut tilemap = Tilemap::new(6, 4);
+    // A diagonal wall splits the grass, except through the corners of the wall tiles.
+    tilemap.write(&LOTile::Wall, &tilemap.select().add_line((2, 0), (5, 3))).unwrap();
+    tilemap.write(&LOTile::Sand, &tilemap.select().add(0, 3)).unwrap();
+    let floor = tilemap.select_where(Tilemap::LAYER1, LOTile::is_floor).unwrap();
+    assert_eq!(floor.len(), 20);
+    assert_eq!(tilemap.select_same_type(Tilemap::LAYER1, &LOTile::Grass).unwrap().len(), 19);
+
+    let four = tilemap.select_connected(Tilemap::LAYER1, (0, 0), Connectivity::Four).unwrap();
+    assert_eq!(render(&four), ["##....", "###...", "####..", ".####."]);
+    let eight = tilemap.select_connected(Tilemap::LAYER1, (0, 0), Connectivity::Eight).unwrap();
+    assert_eq!(eight.len(), 19);
+    let floor = tilemap
+        .select_connected_where(Tilemap::LAYER1, (0, 0), Connectivity::Four, LOTile::is_floor)
+        .unwrap();
+    assert_eq!(floor.len(), four.len() + 1);
+    assert!(tilemap.select_connected_where(Tilemap::LAYER1, (2, 0), Connectivity::Four, LOTile::is_floor).unwrap().is_empty());
+
+    tilemap.write(&LOTile::Snow, &four).unwrap();
+    assert!(matches!(tilemap.layers.0[(3, 4)], LOTile::Snow));
+    assert!(matches!(tilemap.layers.0[(0, 5)], LOTile::Grass));
+    assert!(matches!(tilemap.select_connected(Tilemap::LAYER1, (9, 9), Connectivity::Four), Err(LOError::InvalidPosition { .. })));
+}
+
+#[test]
+fn grows_and_shrinks_selections() {
+    let floor = TileSelection::new(7, 6).add_rect(2, 2, 3, 2);
+    assert_eq!(
+        render(&floor.clone().outline(Connectivity::Eight, Edge::Unselected)),
+        [".......", ".#####.", ".#...#.", ".#...#.", ".#####.", "......."]
+    );
+    assert_eq!(
+        render(&floor.clone().grow(1, Connectivity::Four, Edge::Unselected)),
+        [".......", "..###..", ".#####.", ".#####.", "..###..", "......."]
+    );
+    assert_eq!(render(&floor.clone().inner_outline(Connectivity::Four, Edge::Unselected)), render(&floor));
+    assert!(floor.clone().shrink(1, Connectivity::Four, Edge::Unselected).is_empty());
+
+    // The edge decides whether the room border erodes or grows inwards.
+    let room = TileSelection::all(7, 6);
+    assert_eq!(room.clone().shrink(1, Connectivity::Four, Edge::Unselected).bounding_box(), Some((1, 1, 5, 4)));
+    assert_eq!(room.clone().shrink(1, Connectivity::Four, Edge::Selected).len(), 42);
+    assert_eq!(TileSelection::new(7, 6).grow(1, Connectivity::Eight, Edge::Selected).len(), 22);
+
+    // Opening removes the thin path, closing fills the hole.
+    let blob = TileSelection::new(9, 7).add_rect(1, 1, 4, 4).add_line((5, 2), (7, 2));
+    assert_eq!(
+        render(&blob.clone().open(1, Connectivity::Eight, Edge::Unselected)),
+        [".........", ".####....", ".####....", ".####....", ".####....", ".........", "........."]
+    );
+    let holey = blob.clone().remove(2, 2);
+    assert_eq!(render(&holey.close(1, Connectivity::Eight, Edge::Unselected)), render(&blob));
+
+    let distances = room.distance_to_edge(Connectivity::Four, Edge::Unselected);
+    assert_eq!(distances.row_iter(2).unwrap().copied().collect::<Vec<_>>(), [1, 2, 3, 3, 3, 2, 1].map(Some));
+    let distances = room.remove(0, 0).distance_to_edge(Connectivity::Eight, Edge::Selected);
+    assert_eq!(distances[(0, 0)], Some(0));
+    assert_eq!(distances[(5, 6)], Some(6));
+    assert!(TileSelection::all(2, 2).distance_to_edge(Connectivity::Four, Edge::Selected)[(0, 0)].is_none());
+}
+
+#[test]
+fn views_cells() {
+    let mut tilemap = Tilemap::new(4, 4);
+    tilemap.write(&LOTile::PressurePlate { connections: vec![] }, &tilemap.select().add(1, 1)).unwrap();
+    tilemap.write(&LOTile::PushBlock, &tilemap.select().add(1, 1)).unwrap();
+    let cell = tilemap.cell(1, 1).unwrap();
+    assert_eq!(cell.position, (1, 1));
+    assert_eq!(cell.occupied().map(|(layer, _)| layer).collect::<Vec<_>>(), [Tilemap::LAYER1, Tilemap::LAYER2, Tilemap::LAYER4]);
+    assert!(cell.is_empty_on(Tilemap::LAYER3) && cell.get(5).is_none());
+    assert!(tilemap.cell(4, 0).is_none());
+
+    // Only selected cells with a push block get water.
+    tilemap
+        .write_on_layer_if_cell(Tilemap::LAYER1, &LOTile::Water, &tilemap.select_all(), |cell| {
+            matches!(cell.get(Tilemap::LAYER4), Some(LOTile::PushBlock))
+        })
+        .unwrap();
+    assert_eq!(tilemap.select_same_type(Tilemap::LAYER1, &LOTile::Water).unwrap().len(), 1);
+}
+
+#[test]
+fn writing_trapdoors_keeps_fitting_floors() {
+    let mut tilemap = Tilemap::new(3, 1);
+    tilemap.write(&LOTile::Space, &tilemap.select().add(0, 0)).unwrap();
+    tilemap.write(&LOTile::Ice, &tilemap.select().add(2, 0)).unwrap();
+    tilemap.write(&LOTile::TrapdoorOverPit, &tilemap.select_all()).unwrap();
+    assert_eq!(tilemap.layers.0.as_row_major(), [LOTile::Space, LOTile::Pit, LOTile::Pit]);
+}
+
+#[test]
+fn checks_placements() {
+    let mut tilemap = Tilemap::new(4, 4);
+    tilemap.write(&LOTile::Tree, &tilemap.select().add(0, 0)).unwrap();
+    tilemap.write(&LOTile::LadderUp, &tilemap.select().add(1, 0)).unwrap();
+    tilemap.write(&LOTile::Water, &tilemap.select().add(2, 0)).unwrap();
+    tilemap.write(&LOTile::TrapdoorOverWater, &tilemap.select().add(2, 0)).unwrap();
+    tilemap.write(&LOTile::CrumblyWall, &tilemap.select().add(3, 0)).unwrap();
+    tilemap.write(&LOTile::Slug { direction: LODirection::Up }, &tilemap.select().add(0, 1)).unwrap();
+    tilemap.write(&LOTile::Wall, &tilemap.select().add(1, 1)).unwrap();
+
+    let slug = LOTile::Slug { direction: LODirection::Down };
+    assert!(tilemap.can_place(&slug, 2, 2));
+    assert!(!tilemap.can_place(&slug, 0, 0));
+    assert!(!tilemap.can_place(&slug, 3, 0));
+    assert!(!tilemap.can_place(&slug, 1, 1));
+    assert!(tilemap.can_place(&slug, 2, 0));
+    assert!(!tilemap.can_place(&slug, 4, 0));
+
+    assert!(tilemap.can_place(&LOTile::Wall, 1, 0));
+    assert!(!tilemap.can_place(&LOTile::Wall, 0, 0));
+    assert!(!tilemap.can_place(&LOTile::Wall, 0, 1));
+    assert!(!tilemap.can_place(&LOTile::Rock, 0, 1));
+    assert!(tilemap.can_place(&LOTile::Rock, 2, 2));
+    // Writing a puzzle element of `LAYER2` removes the slug, but a push block can be put onto a plate.
+    assert!(!tilemap.can_place(&LOTile::PressurePlate { connections: vec![] }, 0, 1));
+    assert!(tilemap.can_place(&LOTile::PressurePlate { connections: vec![] }, 2, 2));
+    tilemap.write(&LOTile::PressurePlate { connections: vec![] }, &tilemap.select().add(2, 2)).unwrap();
+    assert!(tilemap.can_place(&LOTile::PushBlock, 2, 2));
+    assert!(!tilemap.can_place(&LOTile::PushBlock, 3, 0));
+    assert!(tilemap.can_place(&LOTile::ToggleDoorInitiallyClosed, 0, 1));
+    assert!(!tilemap.can_place(&LOTile::CrumblyWall, 0, 1));
+
+    assert!(!tilemap.can_place(&LOTile::Grass, 2, 0));
+    assert!(tilemap.can_place(&LOTile::Water, 2, 0));
+    assert!(!tilemap.can_place(&LOTile::TrapdoorOverPit, 2, 2));
+    assert!(tilemap.can_place(&LOTile::Ice, 3, 3));
+    assert!(!tilemap.can_place(&LOTile::None, 3, 3));
+    assert_eq!(Tilemap::layer_of(&slug), Some(Tilemap::LAYER5));
+
+    // Trapdoors keep the tiles on top of them.
+    tilemap.write(&slug, &tilemap.select().add(2, 0)).unwrap();
+    assert!(tilemap.can_place(&LOTile::TrapdoorOverWater, 2, 0));
+    assert!(!tilemap.can_place(&LOTile::TrapdoorOverWater, 0, 1));
+}
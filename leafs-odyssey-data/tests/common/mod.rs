@@ -0,0 +1,282 @@
+//! Helpers shared by the integration tests.
+//!
+//! `fixtures` contains worlds written by the builder and the examples:
+//! - `all_tiles.world` is written by `write_all_tiles_fixture` in `round_trip.rs`, run it with
+//!   `cargo test -- --ignored` after adding tiles to [`all_tiles`].
+//! - `string.world`, `terrain.world` and `image_art.world` are written by the `string-to-world`,
+//!   `terrain-generator` and `image-art` examples.
+
+// Each test file only uses some of the helpers.
+#![allow(dead_code)]
+
+use std::{
+    fs,
+    io::Cursor,
+    path::{Path, PathBuf},
+};
+
+use binrw::BinWrite;
+use leafs_odyssey_data::{builder::*, data::*};
+
+pub fn fixtures_folder() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures")
+}
+
+pub fn fixture(name: &str) -> Vec<u8> {
+    fs::read(fixtures_folder().join(name)).unwrap()
+}
+
+/// Name and bytes of every world in `fixtures`.
+pub fn fixtures() -> Vec<(String, Vec<u8>)> {
+    let mut fixtures: Vec<_> = fs::read_dir(fixtures_folder())
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == "world"))
+        .map(|path| (path.file_name().unwrap().to_string_lossy().into_owned(), fs::read(&path).unwrap()))
+        .collect();
+    assert!(!fixtures.is_empty(), "No fixtures found.");
+    fixtures.sort_by(|(name1, _), (name2, _)| name1.cmp(name2));
+    fixtures
+}
+
+pub fn write_world(world: World) -> Vec<u8> {
+    let mut world = LOWorld::try_from(world).unwrap();
+    let mut output = Cursor::new(vec![]);
+    world.write_world(&mut output).unwrap();
+    output.into_inner()
+}
+
+pub fn round_trip(bytes: &[u8]) -> (LOWorld, Vec<u8>) {
+    let mut world = LOWorld::read_world(&mut Cursor::new(bytes)).unwrap();
+    let mut output = Cursor::new(vec![]);
+    world.write_world(&mut output).unwrap();
+    (world, output.into_inner())
+}
+
+pub fn assert_same_bytes(name: &str, expected: &[u8], actual: &[u8]) {
+    if let Some(offset) = expected.iter().zip(actual).position(|(a, b)| a != b) {
+        panic!("{}: first difference at offset 0x{:X}", name, offset);
+    }
+    assert_eq!(expected.len(), actual.len(), "{}: different length", name);
+}
+
+pub fn stem_bytes(stem: &LOStem) -> Vec<u8> {
+    let mut output = Cursor::new(vec![]);
+    stem.write(&mut output).unwrap();
+    output.into_inner()
+}
+
+pub fn replace_first(bytes: &mut [u8], pattern: &[u8], replacement: &[u8]) {
+    let offset = bytes.windows(pattern.len()).position(|window| window == pattern).unwrap();
+    bytes[offset..offset + replacement.len()].copy_from_slice(replacement);
+}
+
+/// `all_tiles.world`, with values which neither the data nor the builder expose changed from what they write.
+pub fn all_tiles_with_unknown_values() -> Vec<u8> {
+    let mut bytes = fixture("all_tiles.world");
+    // World header, after its magic.
+    bytes[4..8].copy_from_slice(&9u32.to_le_bytes());
+    // Zone and room table.
+    replace_first(&mut bytes, &1007u32.to_le_bytes(), &1234u32.to_le_bytes());
+    replace_first(&mut bytes, &1002u32.to_le_bytes(), &5678u32.to_le_bytes());
+    // Layer, around its width and height.
+    replace_first(
+        &mut bytes,
+        &[0, 0, 0, 0, 0, 24, 0, 16, 0, 1, 1, 0, 0, 0x80, 0x3F],
+        &[1, 2, 3, 4, 5, 24, 0, 16, 0, 6, 7, 8, 9, 10, 11],
+    );
+    // First room, after its name and size.
+    replace_first(&mut bytes, b"None\0\x18\0\x10\0\0\0\0\0", b"None\0\x18\0\x10\0\x2A\0\0\0");
+    // Zone map and first room, after their magic.
+    replace_first(&mut bytes, b"tile_zone_map\0\0\0\x01\0\0\0", b"tile_zone_map\0\x0B\0\x0C\0\0\0");
+    replace_first(&mut bytes, b"tilemap_edit\0\0\0", b"tilemap_edit\0\x0D\0");
+    bytes
+}
+
+/// One of each tile, with payloads where possible.
+pub fn all_tiles() -> Vec<LOTile> {
+    let connections = || vec![
+        LOConnection { x_position: 0, y_position: 0 },
+        LOConnection { x_position: 23, y_position: 15 },
+    ];
+    let tiles = vec![
+        LOTile::None,
+        LOTile::Grass,
+        LOTile::Dirt,
+        LOTile::DirtPath,
+        LOTile::Sand,
+        LOTile::Snow,
+        LOTile::OvergrownGrass,
+        LOTile::RedFlowers,
+        LOTile::YellowFlowers,
+        LOTile::DeadGrass,
+        LOTile::SnowyGrass,
+        LOTile::Gravel,
+        LOTile::PineNeedles,
+        LOTile::WoodenFloor,
+        LOTile::StoneFloor,
+        LOTile::TileFloor,
+        LOTile::MarbleFloor,
+        LOTile::CobblestonePath,
+        LOTile::Water,
+        LOTile::Space,
+        LOTile::Sky,
+        LOTile::Cloud,
+        LOTile::Pit,
+        LOTile::Wall,
+        LOTile::WallWithWindow,
+        LOTile::WoodenWall,
+        LOTile::WoodenWallWithWindow,
+        LOTile::BrickWall,
+        LOTile::BrickWallWithWindow,
+        LOTile::StoneBrickWall,
+        LOTile::StoneBrickWallWithWindow,
+        LOTile::Cliff,
+        LOTile::RoughStone,
+        LOTile::Bush,
+        LOTile::PineTree,
+        LOTile::AutumnTree,
+        LOTile::Tree,
+        LOTile::DeadTree,
+        LOTile::Pillar,
+        LOTile::WoodenFence,
+        LOTile::IronFence,
+        LOTile::Rock,
+        LOTile::Cattails,
+        LOTile::TallGrass,
+        LOTile::Curtain,
+        LOTile::Lamppost,
+        LOTile::HotCoals,
+        LOTile::Ice,
+        LOTile::PacificFloor,
+        LOTile::BlockBarrier,
+        LOTile::SteppingStone,
+        LOTile::Waypoint,
+        LOTile::LadderUp,
+        LOTile::LadderDown,
+        LOTile::TrapdoorOverPit,
+        LOTile::TrapdoorOverWater,
+        LOTile::TrapdoorOverHotCoals,
+        LOTile::TrapdoorOverIce,
+        LOTile::TrapdoorOverPacificFloor,
+        LOTile::GoalStar,
+        LOTile::PressurePlate { connections: connections() },
+        LOTile::PressurePlate { connections: vec![] },
+        LOTile::SacrificeAltar { connections: connections() },
+        LOTile::ToggleFloorInitiallyClosed,
+        LOTile::ToggleFloorInitiallyOpen,
+        LOTile::CrumblyWall,
+        LOTile::CrumblyBrickWall,
+        LOTile::CrumblyWoodenWall,
+        LOTile::CrumblyStoneBrickWall,
+        LOTile::MonsterGate,
+        LOTile::InvertedMonsterGate,
+        LOTile::ToggleDoorInitiallyClosed,
+        LOTile::ToggleDoorInitiallyOpen,
+        LOTile::PrimeKey,
+        LOTile::TerraKey,
+        LOTile::SkyKey,
+        LOTile::InfernalKey,
+        LOTile::StarKey,
+        LOTile::PushBlock,
+        LOTile::MultiPushBlock,
+        LOTile::MonsterBlock,
+        LOTile::StartPoint { direction: LODirection::Left },
+        LOTile::PrimeDoor,
+        LOTile::TerraDoor,
+        LOTile::SkyDoor,
+        LOTile::InfernalDoor,
+        LOTile::StarDoor,
+        LOTile::StatueRubble,
+        LOTile::PoisonTrail,
+        LOTile::Sign { text: "Hello World".into() },
+        LOTile::Sign { text: "".into() },
+        LOTile::Stack {
+            tiles: vec![
+                LOStackElement::new(LOStackTile::PushBlock, LOStackDirection::Up, vec![]),
+                LOStackElement::new(LOStackTile::ToggleSwitch, LOStackDirection::Right, connections()),
+                LOStackElement::new(LOStackTile::PrimeKey, LOStackDirection::Down, vec![]),
+            ],
+            unknown1: 0,
+        },
+        LOTile::Stack { tiles: vec![], unknown1: 0 },
+        LOTile::ToggleSwitch { connections: connections() },
+        LOTile::AngryEye,
+        LOTile::BombBug { direction: LODirection::Up },
+        LOTile::Statue,
+        LOTile::Slug { direction: LODirection::Right },
+        LOTile::FlyingSnake { direction: LODirection::Down },
+        LOTile::Unknown { id: 0x99, payload: vec![] },
+    ];
+
+    for tile in &tiles {
+        // Fails to compile if a variant is added, it then needs to be added above as well.
+        match tile {
+            LOTile::None | LOTile::Grass | LOTile::Dirt | LOTile::DirtPath | LOTile::Sand | LOTile::Snow
+            | LOTile::OvergrownGrass | LOTile::RedFlowers | LOTile::YellowFlowers | LOTile::DeadGrass
+            | LOTile::SnowyGrass | LOTile::Gravel | LOTile::PineNeedles | LOTile::WoodenFloor | LOTile::StoneFloor
+            | LOTile::TileFloor | LOTile::MarbleFloor | LOTile::CobblestonePath | LOTile::Water | LOTile::Space
+            | LOTile::Sky | LOTile::Cloud | LOTile::Pit | LOTile::Wall | LOTile::WallWithWindow | LOTile::WoodenWall
+            | LOTile::WoodenWallWithWindow | LOTile::BrickWall | LOTile::BrickWallWithWindow
+            | LOTile::StoneBrickWall | LOTile::StoneBrickWallWithWindow | LOTile::Cliff | LOTile::RoughStone
+            | LOTile::Bush | LOTile::PineTree | LOTile::AutumnTree | LOTile::Tree | LOTile::DeadTree
+            | LOTile::Pillar | LOTile::WoodenFence | LOTile::IronFence | LOTile::Rock | LOTile::Cattails
+            | LOTile::TallGrass | LOTile::Curtain | LOTile::Lamppost | LOTile::HotCoals | LOTile::Ice
+            | LOTile::PacificFloor | LOTile::BlockBarrier | LOTile::SteppingStone | LOTile::Waypoint
+            | LOTile::LadderUp | LOTile::LadderDown | LOTile::TrapdoorOverPit | LOTile::TrapdoorOverWater
+            | LOTile::TrapdoorOverHotCoals | LOTile::TrapdoorOverIce | LOTile::TrapdoorOverPacificFloor
+            | LOTile::GoalStar | LOTile::PressurePlate { .. } | LOTile::SacrificeAltar { .. }
+            | LOTile::ToggleFloorInitiallyClosed | LOTile::ToggleFloorInitiallyOpen | LOTile::CrumblyWall
+            | LOTile::CrumblyBrickWall | LOTile::CrumblyWoodenWall | LOTile::CrumblyStoneBrickWall
+            | LOTile::MonsterGate | LOTile::InvertedMonsterGate | LOTile::ToggleDoorInitiallyClosed
+            | LOTile::ToggleDoorInitiallyOpen | LOTile::PrimeKey | LOTile::TerraKey | LOTile::SkyKey
+            | LOTile::InfernalKey | LOTile::StarKey | LOTile::PushBlock | LOTile::MultiPushBlock
+            | LOTile::MonsterBlock | LOTile::StartPoint { .. } | LOTile::PrimeDoor | LOTile::TerraDoor
+            | LOTile::SkyDoor | LOTile::InfernalDoor | LOTile::StarDoor | LOTile::StatueRubble
+            | LOTile::PoisonTrail | LOTile::Sign { .. } | LOTile::Stack { .. } | LOTile::ToggleSwitch { .. }
+            | LOTile::AngryEye | LOTile::BombBug { .. } | LOTile::Statue | LOTile::Slug { .. }
+            | LOTile::FlyingSnake { .. } | LOTile::Unknown { .. } => {}
+        }
+    }
+    tiles
+}
+
+pub fn all_music() -> Vec<LOMusic> {
+    (-1..=14).map(|value| LOMusic::try_from(value).unwrap()).collect()
+}
+
+/// The layer the game would put the tile on.
+fn layer_of(tile: &LOTile) -> u8 {
+    if tile.is_floor() || tile.is_wall() {
+        Tilemap::LAYER1
+    } else if tile.is_puzzle_layer3() {
+        Tilemap::LAYER3
+    } else if tile.is_puzzle_layer4() {
+        Tilemap::LAYER4
+    } else if tile.is_puzzle_layer5() || tile.is_monster() {
+        Tilemap::LAYER5
+    } else {
+        Tilemap::LAYER2
+    }
+}
+
+/// One room per music, with all tiles in the first room.
+pub fn all_tiles_world() -> World {
+    let mut world = World::new().with_metadata("All Tiles", "Every tile and music.");
+    for (i, music) in all_music().into_iter().enumerate() {
+        let room = world.new_room((i as i16, 0, 0)).with_metadata(&format!("{:?}", music), music);
+        world.rooms.push(room);
+    }
+
+    let tilemap = &mut world.rooms[0].tilemap;
+    let width = tilemap.get_width() as usize;
+    let mut next_positions = [0; 5];
+    for tile in all_tiles() {
+        let layer = layer_of(&tile);
+        let position = &mut next_positions[layer as usize];
+        let selection = tilemap.select().add(*position % width, *position / width);
+        tilemap.write_on_layer(layer, &tile, &selection).unwrap();
+        *position += 1;
+    }
+    world
+}
@@ -0,0 +1,63 @@
+mod common;
+
+use std::io::Cursor;
+
+use common::*;
+use leafs_odyssey_data::{builder::*, data::*, error::LOError, validate::LODiagnostic};
+
+fn through_builder(bytes: &[u8]) -> Vec<u8> {
+    let world = LOWorld::read_world(&mut Cursor::new(bytes)).unwrap();
+    write_world(World::try_from(world).unwrap())
+}
+
+#[test]
+fn fixtures_round_trip_through_builder() {
+    for (name, bytes) in fixtures() {
+        assert_same_bytes(&name, &bytes, &through_builder(&bytes));
+    }
+
+    // Values the builder does not expose.
+    let bytes = all_tiles_with_unknown_values();
+    assert_same_bytes("patched", &bytes, &through_builder(&bytes));
+}
+
+#[test]
+fn loaded_world_matches_built_world() {
+    let bytes = write_world(all_tiles_world());
+    let world = World::try_from(LOWorld::read_world(&mut Cursor::new(bytes)).unwrap()).unwrap();
+    let expected = all_tiles_world();
+
+    assert_eq!(world.name, expected.name);
+    assert_eq!(world.author.guid, expected.author.guid);
+    assert_eq!(world.start_room, Some(1));
+    assert_eq!(world.rooms.len(), expected.rooms.len());
+    for (room, expected) in world.rooms.iter().zip(&expected.rooms) {
+        assert_eq!(room.id, expected.id);
+        assert_eq!(room.position, expected.position);
+        assert_eq!(room.music, expected.music);
+        assert_eq!(room.tilemap.layers, expected.tilemap.layers);
+    }
+}
+
+#[test]
+fn unreferenced_stems_are_not_converted() {
+    let bytes = fixture("string.world");
+    let world = LOWorld::read_world(&mut Cursor::new(&bytes)).unwrap();
+    let mut room = world.stems[1].content.clone();
+    let LOStemContent::TileMapEdit { id, .. } = &mut room else {
+        panic!("Second stem is not a room.");
+    };
+    *id = 99;
+
+    // Stems are read until the end of the file, no matter how many rooms the zone lists.
+    let unreferenced = [stem_bytes(&LOStem::from_content(room)), stem_bytes(&world.stems[0]), stem_bytes(&world.stems[1])];
+    for stem in unreferenced {
+        let mut bytes = bytes.clone();
+        bytes.extend(stem);
+        let world = LOWorld::read_world(&mut Cursor::new(&bytes)).unwrap();
+        let diagnostic = LODiagnostic::UnreferencedStem { stem_index: 2 };
+        // Copies of the room also copy its start point.
+        assert!(world.validate().contains(&diagnostic));
+        assert!(matches!(World::try_from(world), Err(LOError::InvalidWorld { diagnostic: found }) if found == diagnostic));
+    }
+}
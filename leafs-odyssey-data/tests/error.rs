@@ -0,0 +1,51 @@
+mod common;
+
+use std::io::Cursor;
+
+use binrw::BinWrite;
+use common::*;
+use leafs_odyssey_data::{
+    data::*,
+    error::{LOError, LOErrorContext},
+};
+
+fn find(bytes: &[u8], pattern: &[u8]) -> usize {
+    let offset = bytes.windows(pattern.len()).position(|window| window == pattern).unwrap();
+    assert_eq!(bytes.windows(pattern.len()).rposition(|window| window == pattern), Some(offset));
+    offset
+}
+
+#[test]
+fn errors_locate_corrupted_layers() {
+    let mut bytes = fixture("image_art.world");
+    let world = LOWorld::read_world(&mut Cursor::new(&bytes)).unwrap();
+    let LOStemContent::TileMapEdit { id, layers, .. } = &world.stems[2].content else {
+        panic!("Third stem is not a room.");
+    };
+    // Layers follow each other, the first one is the only one showing the image.
+    let mut layer = Cursor::new(vec![]);
+    layers[0].write(&mut layer).unwrap();
+    let layer = layer.into_inner();
+    let stem = stem_bytes(&world.stems[2]);
+    let offset = find(&bytes, &stem) + find(&stem, &layer) + layer.len();
+    // The tile count no longer matches width and height.
+    bytes[offset + 13..offset + 17].copy_from_slice(&u32::MAX.to_le_bytes());
+
+    let expected = LOErrorContext {
+        offset: Some(offset as u64),
+        stem_index: Some(2),
+        room_id: Some(*id),
+        layer: Some(1),
+    };
+    let error = LOWorld::read_world(&mut Cursor::new(&bytes)).err().unwrap();
+    assert!(matches!(error, LOError::Binary { .. }), "{}", error);
+    assert_eq!(error.context(), Some(&expected));
+
+    // The reader only knows the room id once it read the zone stem.
+    let mut reader = LOWorldReader::new(Cursor::new(&bytes)).unwrap();
+    let without_id = LOErrorContext { room_id: None, ..expected.clone() };
+    assert_eq!(reader.read_room_stem(1).err().unwrap().context(), Some(&without_id));
+    assert_eq!(reader.read_room_stem_by_id(*id).err().unwrap().context(), Some(&expected));
+    assert_eq!(reader.read_room_stem(1).err().unwrap().context(), Some(&expected));
+    assert!(reader.read_room_stem(0).is_ok());
+}
@@ -0,0 +1,55 @@
+use std::io::Cursor;
+
+use leafs_odyssey_data::{builder::*, data::LOWorld, error::LOError, guid::*};
+use rand::{rngs::StdRng, SeedableRng};
+
+#[test]
+fn parses_and_prints_ui_format() {
+    let guid: AuthorGuid = "0123abcd-FFFFFFFF".parse().unwrap();
+    assert_eq!(guid.segments(), [0x0123ABCD, 0xFFFFFFFF]);
+    assert_eq!(guid.to_string(), "0123ABCD-FFFFFFFF");
+
+    let guid: WorldGuid = "00000000".parse().unwrap();
+    assert_eq!(guid, WorldGuid::default());
+}
+
+#[test]
+fn keeps_upper_halves() {
+    let guid = WorldGuid::from_segments([0x1234_0000_0001]);
+    assert_eq!(guid.to_string(), "0000123400000001");
+    assert_eq!(guid.to_string().parse::<WorldGuid>().unwrap(), guid);
+}
+
+#[test]
+fn rejects_invalid() {
+    for guid in ["", "0123ABCD", "0123ABCD-", "0123ABCD-0123ABCD-0123ABCD", "0123ABCD-+123", "0123ABCD-XYZ"] {
+        assert!(guid.parse::<AuthorGuid>().is_err(), "{}", guid);
+    }
+    // Only the first author segment may use the upper half.
+    assert!("00000001-100000000".parse::<AuthorGuid>().is_err());
+    let guid: AuthorGuid = "100000000-0000000000000001".parse().unwrap();
+    assert_eq!(guid.segments(), [0x1_0000_0000, 1]);
+}
+
+#[test]
+fn random_is_seedable() {
+    let first = AuthorGuid::random(&mut StdRng::seed_from_u64(42));
+    let second = AuthorGuid::random(&mut StdRng::seed_from_u64(42));
+    assert_eq!(first, second);
+    assert!(first.segments().iter().all(|segment| *segment <= u32::MAX as u64));
+}
+
+#[test]
+fn second_author_segment_has_to_fit_32_bits() {
+    let write = |guid| {
+        let mut world = World::new().with_identity(WorldGuid::default(), Author { name: "Author".into(), guid });
+        let room = world.new_room((0, 0, 0));
+        world.add_room(room);
+        LOWorld::try_from(world).unwrap().write_world(&mut Cursor::new(vec![]))
+    };
+    write(AuthorGuid::from_segments([0x1_0000_0000, u32::MAX as u64])).unwrap();
+
+    let error = write(AuthorGuid::from_segments([1, 0x1_0000_0000])).unwrap_err();
+    assert!(matches!(&error, LOError::Binary { context, .. } if context.stem_index == Some(0)));
+    assert!(error.to_string().contains("Invalid GUID '00000001-0000000100000000'"), "{}", error);
+}
@@ -0,0 +1,78 @@
+mod common;
+
+use std::io::Cursor;
+
+use binrw::NullString;
+use common::*;
+use leafs_odyssey_data::{data::*, guid::*};
+
+/// Values of the zone fields which are known to this crate.
+fn zone_fields(stem: &LOStem) -> (u32, &NullString, &WorldGuid, &AuthorGuid, u32, u32) {
+    let LOStemContent::TileZoneMap { start_room, author, guid_world, guid_author, world_revision, compatibility, .. } =
+        &stem.content
+    else {
+        panic!("Stem is not a zone.");
+    };
+    (*start_room, author, guid_world, guid_author, *world_revision, *compatibility)
+}
+
+/// Values of the room fields which are known to this crate.
+fn room_fields(stem: &LOStem) -> (&LOMusic, u32) {
+    let LOStemContent::TileMapEdit { music, revision, .. } = &stem.content else {
+        panic!("Stem is not a room.");
+    };
+    (music, *revision)
+}
+
+fn stem_extra_fields(stem: &LOStem) -> &[LOField] {
+    match &stem.content {
+        LOStemContent::TileZoneMap { extra_fields, .. } | LOStemContent::TileMapEdit { extra_fields, .. } => extra_fields,
+    }
+}
+
+#[test]
+fn stems_follow_other_layouts() {
+    let bytes = fixture("image_art.world");
+    let (original, _) = round_trip(&bytes);
+    let (mut world, _) = round_trip(&bytes);
+
+    // Zone fields in reverse order, without the description, and with an additional field.
+    let LOStemContent::TileZoneMap { metadata, extra_fields, .. } = &mut world.stems[0].content else {
+        panic!("First stem is not a zone.");
+    };
+    metadata.fields.reverse();
+    metadata.fields.retain(|field| field.name().0 != b"#");
+    metadata.fields.insert(2, LOMetadataField::Int { name: "z".into(), default: 7, _unknown1: [0, 0] });
+    extra_fields.push(LOField::new(b"z", LOFieldValue::Int(8)));
+    // Room fields in reverse order, with an additional field which only the first room sets.
+    for (i, stem) in world.stems[1..].iter_mut().enumerate() {
+        let LOStemContent::TileMapEdit { metadata, extra_fields, .. } = &mut stem.content else {
+            panic!("Stem {} is not a room.", i + 1);
+        };
+        let entry = metadata.entries.iter_mut().find(|entry| entry.id == LOTileMapMetadata::STEM_ENTRY_ID).unwrap();
+        entry.fields.reverse();
+        entry.fields.insert(1, LOMetadataField::Long { name: "y".into(), default: 1, _unknown1: [0, 0] });
+        if i == 0 {
+            extra_fields.push(LOField::new(b"y", LOFieldValue::Long(2)));
+        }
+    }
+
+    let mut output = Cursor::new(vec![]);
+    world.write_world(&mut output).unwrap();
+    let bytes = output.into_inner();
+    let (read, output) = round_trip(&bytes);
+    assert_same_bytes("other layout", &bytes, &output);
+    assert_eq!(read.detect_version(), None);
+
+    assert_eq!(zone_fields(&read.stems[0]), zone_fields(&original.stems[0]));
+    for (i, (stem, original)) in read.stems.iter().zip(&original.stems).enumerate().skip(1) {
+        assert_eq!(room_fields(stem), room_fields(original), "stem {}", i);
+    }
+    let LOStemContent::TileZoneMap { description, .. } = &read.stems[0].content else {
+        panic!("First stem is not a zone.");
+    };
+    assert!(description.is_empty());
+    assert_eq!(stem_extra_fields(&read.stems[0]), &[LOField::new(b"z", LOFieldValue::Int(8))]);
+    assert_eq!(stem_extra_fields(&read.stems[1]), &[LOField::new(b"y", LOFieldValue::Long(2))]);
+    assert_eq!(stem_extra_fields(&read.stems[2]), &[LOField::new(b"y", LOFieldValue::Long(1))]);
+}
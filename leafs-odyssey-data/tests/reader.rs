@@ -0,0 +1,65 @@
+mod common;
+
+use std::{
+    cell::Cell,
+    io::{Cursor, Read, Seek, SeekFrom},
+    rc::Rc,
+};
+
+use common::*;
+use leafs_odyssey_data::data::*;
+
+/// Counts seeks, to tell how much a [`LOWorldReader`] reads.
+struct CountingReader {
+    inner: Cursor<Vec<u8>>,
+    seeks: Rc<Cell<usize>>,
+}
+
+impl Read for CountingReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.inner.read(buf)
+    }
+}
+
+impl Seek for CountingReader {
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        self.seeks.set(self.seeks.get() + 1);
+        self.inner.seek(pos)
+    }
+}
+
+#[test]
+fn reader_matches_read_world() {
+    for (name, bytes) in fixtures() {
+        let mut world = LOWorld::read_world(&mut Cursor::new(&bytes)).unwrap();
+
+        let seeks = Rc::new(Cell::new(0));
+        let inner = Cursor::new(bytes.clone());
+        let mut reader = LOWorldReader::new(CountingReader { inner, seeks: seeks.clone() }).unwrap();
+        assert_eq!(reader.room_count(), world.stems.len() - 1, "{}", name);
+
+        for (index, stem) in world.stems[1..].iter().enumerate() {
+            let LOStemContent::TileMapEdit { id, .. } = &stem.content else {
+                panic!("{}: stem {} is not a room.", name, index + 1);
+            };
+            let before = seeks.get();
+            assert_eq!(stem_bytes(&reader.read_room_stem(index).unwrap()), stem_bytes(stem), "{}", name);
+            let by_index = seeks.get() - before;
+            let by_id = reader.read_room_stem_by_id(*id).unwrap().unwrap();
+            assert_eq!(stem_bytes(&by_id), stem_bytes(stem), "{}", name);
+            // Only the first lookup reads the zone stem.
+            if index > 0 {
+                assert_eq!(seeks.get() - before, 2 * by_index, "{}", name);
+            }
+        }
+        assert!(reader.read_room_stem_by_id(u32::MAX).unwrap().is_none());
+        assert_eq!(stem_bytes(&reader.read_zone_stem().unwrap()), stem_bytes(&world.stems[0]), "{}", name);
+
+        let mut read = reader.into_world().unwrap();
+        let mut expected = Cursor::new(vec![]);
+        world.write_world(&mut expected).unwrap();
+        let mut output = Cursor::new(vec![]);
+        read.write_world(&mut output).unwrap();
+        assert_same_bytes(&name, &expected.into_inner(), &output.into_inner());
+    }
+}
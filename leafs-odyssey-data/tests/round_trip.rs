@@ -0,0 +1,53 @@
+//! Reading a world and writing it again has to result in the exact same bytes.
+
+mod common;
+
+use std::fs;
+
+use common::*;
+use leafs_odyssey_data::data::*;
+
+#[test]
+#[ignore = "regenerates a fixture"]
+fn write_all_tiles_fixture() {
+    fs::write(fixtures_folder().join("all_tiles.world"), write_world(all_tiles_world())).unwrap();
+}
+
+#[test]
+fn fixtures_round_trip() {
+    for (name, bytes) in fixtures() {
+        let (_, output) = round_trip(&bytes);
+        assert_same_bytes(&name, &bytes, &output);
+    }
+}
+
+#[test]
+fn every_tile_round_trips() {
+    let bytes = write_world(all_tiles_world());
+    let (world, output) = round_trip(&bytes);
+    assert_same_bytes("all tiles", &bytes, &output);
+
+    let LOStemContent::TileMapEdit { layers, .. } = &world.stems[1].content else {
+        panic!("Second stem is not a room.");
+    };
+    let expected_layers = all_tiles_world().rooms.remove(0).tilemap.into_layers();
+    for (layer, expected_layer) in layers.iter().zip(&expected_layers) {
+        assert_eq!(layer.tiles, expected_layer.tiles);
+    }
+}
+
+#[test]
+fn every_music_round_trips() {
+    let bytes = write_world(all_tiles_world());
+    let (world, _) = round_trip(&bytes);
+
+    let read_music: Vec<LOMusic> = world
+        .stems
+        .iter()
+        .filter_map(|stem| match &stem.content {
+            LOStemContent::TileMapEdit { music, .. } => Some(music.clone()),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(read_music, all_music());
+}
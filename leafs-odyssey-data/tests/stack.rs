@@ -0,0 +1,34 @@
+mod common;
+
+use common::*;
+use leafs_odyssey_data::data::*;
+
+#[test]
+fn stackable_tiles_map_back() {
+    let mut stackable = 0;
+    for tile in all_tiles() {
+        let Ok(stack_tile) = LOStackTile::try_from(&tile) else {
+            continue;
+        };
+        stackable += 1;
+
+        let (direction, connections) = match &tile {
+            LOTile::StartPoint { direction }
+            | LOTile::BombBug { direction }
+            | LOTile::Slug { direction }
+            | LOTile::FlyingSnake { direction } => (direction.clone().into(), vec![]),
+            LOTile::ToggleSwitch { connections } => (LOStackDirection::Up, connections.clone()),
+            _ => (LOStackDirection::Up, vec![]),
+        };
+        let element = LOStackElement::new(stack_tile, direction, connections);
+        let expected = match &tile {
+            LOTile::Sign { .. } => LOTile::Sign { text: "".into() },
+            tile => tile.clone(),
+        };
+        assert_eq!(LOTile::from(&element), expected);
+    }
+    assert!(stackable > 0);
+
+    assert!(LOStackTile::try_from(&LOTile::PressurePlate { connections: vec![] }).is_err());
+    assert!(LOStackTile::try_from(&LOTile::ToggleDoorInitiallyOpen).is_err());
+}
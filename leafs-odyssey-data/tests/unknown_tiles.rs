@@ -0,0 +1,51 @@
+mod common;
+
+use std::io::Cursor;
+
+use binrw::BinWrite;
+use common::*;
+use leafs_odyssey_data::{builder::*, data::*};
+
+#[test]
+fn unknown_tile_payloads_are_kept() {
+    let mut world = World::new();
+    let room = world.new_room((0, 0, 0));
+    world.add_room(room);
+    let mut world = LOWorld::try_from(world).unwrap();
+
+    // Payload of an int and a long, as a newer game version might describe it.
+    let payload: Vec<u8> = [7i32.to_le_bytes().as_slice(), &0x0102_0304_0506_0708u64.to_le_bytes()].concat();
+    let LOStemContent::TileMapEdit { metadata, layers, .. } = &mut world.stems[1].content else {
+        panic!("Second stem is not a room.");
+    };
+    let mut entry = metadata.entries[0].clone();
+    entry.id = 999;
+    entry.fields = vec![
+        LOMetadataField::Int { name: "a".into(), default: 0, _unknown1: [0, 0] },
+        LOMetadataField::Long { name: "b".into(), default: 0, _unknown1: [0, 0] },
+    ];
+    metadata.entries.push(entry);
+    let tile = LOTile::Unknown { id: 999, payload: payload.clone() };
+    layers[Tilemap::LAYER2 as usize].tiles[5] = tile.clone();
+
+    // The payload is read as described, the grass after it is read as a tile again.
+    let mut tile_bytes = Cursor::new(vec![]);
+    tile.write(&mut tile_bytes).unwrap();
+    LOTile::Grass.write(&mut tile_bytes).unwrap();
+    tile_bytes.set_position(0);
+    let read = LOTile::read_with_metadata(&mut tile_bytes, binrw::Endian::Little, metadata).unwrap();
+    assert_eq!(read, tile);
+    assert_eq!(tile_bytes.position(), 4 + payload.len() as u64);
+    let next = LOTile::read_with_metadata(&mut tile_bytes, binrw::Endian::Little, metadata).unwrap();
+    assert_eq!(next, LOTile::Grass);
+
+    let mut bytes = Cursor::new(vec![]);
+    world.write_world(&mut bytes).unwrap();
+    let bytes = bytes.into_inner();
+    let (world, output) = round_trip(&bytes);
+    assert_same_bytes("unknown tile", &bytes, &output);
+    let LOStemContent::TileMapEdit { layers, .. } = &world.stems[1].content else {
+        panic!("Second stem is not a room.");
+    };
+    assert_eq!(layers[Tilemap::LAYER2 as usize].tiles[5], tile);
+}
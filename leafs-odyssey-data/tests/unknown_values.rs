@@ -0,0 +1,55 @@
+mod common;
+
+use std::io::Cursor;
+
+use common::*;
+use leafs_odyssey_data::{data::*, guid::*};
+
+#[test]
+fn unknown_values_are_preserved() {
+    let bytes = all_tiles_with_unknown_values();
+    let (mut world, output) = round_trip(&bytes);
+    assert_same_bytes("patched", &bytes, &output);
+    let mut output = Cursor::new(vec![]);
+    LOWorldReader::new(Cursor::new(&bytes)).unwrap().into_world().unwrap().write_world(&mut output).unwrap();
+    assert_same_bytes("read lazily", &bytes, &output.into_inner());
+
+    for stem in &mut world.stems {
+        match &mut stem.content {
+            LOStemContent::TileZoneMap { guid_world, guid_author, .. } => {
+                *guid_world = WorldGuid::from_segments([0x1_0000_0001]);
+                *guid_author = AuthorGuid::from_segments([0x2_0000_0002, 3]);
+            }
+            LOStemContent::TileMapEdit { layers, .. } => {
+                for tile in layers.iter_mut().flat_map(|layer| &mut layer.tiles) {
+                    if let LOTile::Stack { unknown1, .. } = tile {
+                        *unknown1 = 3;
+                    }
+                }
+            }
+        }
+    }
+    let mut bytes = Cursor::new(vec![]);
+    world.write_world(&mut bytes).unwrap();
+    let bytes = bytes.into_inner();
+    let (world, output) = round_trip(&bytes);
+    assert_same_bytes("modified", &bytes, &output);
+
+    let LOStemContent::TileZoneMap { guid_world, guid_author, .. } = &world.stems[0].content else {
+        panic!("First stem is not a zone.");
+    };
+    assert_eq!(guid_world.to_string(), "0000000100000001");
+    assert_eq!(guid_author.to_string(), "0000000200000002-00000003");
+    let LOStemContent::TileMapEdit { layers, .. } = &world.stems[1].content else {
+        panic!("Second stem is not a room.");
+    };
+    let stack_unknowns: Vec<u32> = layers
+        .iter()
+        .flat_map(|layer| &layer.tiles)
+        .filter_map(|tile| match tile {
+            LOTile::Stack { unknown1, .. } => Some(*unknown1),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(stack_unknowns, vec![3, 3]);
+}
@@ -0,0 +1,88 @@
+mod common;
+
+use std::io::Cursor;
+
+use common::fixture;
+use leafs_odyssey_data::{builder::*, data::*, error::LOError, validate::LODiagnostic};
+
+fn read_fixture(name: &str) -> (LOWorld, Vec<u8>) {
+    let bytes = fixture(name);
+    (LOWorld::read_world(&mut Cursor::new(&bytes)).unwrap(), bytes)
+}
+
+fn write(world: &mut LOWorld) -> Vec<u8> {
+    let mut output = Cursor::new(vec![]);
+    world.write_world(&mut output).unwrap();
+    output.into_inner()
+}
+
+fn zone_map(world: &mut LOWorld) -> &mut LOStemContent {
+    &mut world.stems[0].content
+}
+
+#[test]
+fn validates_fixtures() {
+    for name in ["all_tiles.world", "string.world", "terrain.world"] {
+        let (world, _) = read_fixture(name);
+        assert_eq!(world.validate(), vec![], "{}", name);
+    }
+    // The example does not place a start point.
+    let (world, _) = read_fixture("image_art.world");
+    assert_eq!(world.validate(), vec![LODiagnostic::StartPointCount { found: 0 }]);
+}
+
+#[test]
+fn zone_map_room_count_is_kept() {
+    let (mut world, bytes) = read_fixture("string.world");
+    let LOStemContent::TileZoneMap { room_info, listed_room_count, .. } = zone_map(&mut world) else {
+        panic!("First stem is not a zone map.");
+    };
+    let rooms = room_info.len();
+    assert_eq!(*listed_room_count, Some(rooms as u32));
+    *listed_room_count = Some(rooms as u32 + 2);
+
+    assert_eq!(world.validate(), vec![LODiagnostic::ZoneMapRoomCount { found: rooms as u32 + 2, expected: rooms }]);
+    let written = write(&mut world);
+    assert_ne!(written, bytes);
+    assert_eq!(written.len(), bytes.len());
+
+    // The wrong count is read and written back as it is.
+    let mut read = LOWorld::read_world(&mut Cursor::new(&written)).unwrap();
+    assert_eq!(write(&mut read), written);
+    assert_eq!(read.validate(), world.validate());
+    assert!(matches!(
+        World::try_from(read),
+        Err(LOError::InvalidWorld { diagnostic: LODiagnostic::ZoneMapRoomCount { .. } })
+    ));
+}
+
+#[test]
+fn detects_inconsistencies() {
+    let mut world = World::new();
+    let room = world.new_room((0, 0, 0));
+    world.add_room(room);
+    world.set_tile((1, 1, 0), &LOTile::StartPoint { direction: LODirection::Up }).unwrap();
+    let mut world = LOWorld::try_from(world).unwrap();
+    assert_eq!(world.validate(), vec![]);
+
+    let LOStemContent::TileZoneMap { start_room, room_info, .. } = zone_map(&mut world) else {
+        panic!("First stem is not a zone map.");
+    };
+    *start_room = 5;
+    room_info.push(room_info[0].clone());
+    let LOStemContent::TileMapEdit { layers, .. } = &mut world.stems[1].content else {
+        panic!("Second stem is not a tilemap.");
+    };
+    layers[Tilemap::LAYER2 as usize].tiles[0] = LOTile::PressurePlate {
+        connections: vec![LOConnection { x_position: 24, y_position: 0 }],
+    };
+    layers[Tilemap::LAYER1 as usize].tiles.pop();
+
+    assert_eq!(world.validate(), vec![
+        LODiagnostic::RoomCountMismatch { world: 1, zone: 1, zone_map: 2 },
+        LODiagnostic::DuplicateRoomId { room_id: 1 },
+        LODiagnostic::MissingStartRoom { room_id: 5 },
+        LODiagnostic::TileCount { room_id: 1, layer: 0, found: 383, expected: 384 },
+        LODiagnostic::ConnectionOutOfBounds { room_id: 1, layer: 1, position: (0, 0), connection: (24, 0) },
+    ]);
+}
@@ -0,0 +1,43 @@
+mod common;
+
+use std::io::Cursor;
+
+use common::*;
+use leafs_odyssey_data::data::*;
+
+#[test]
+fn detects_and_sets_versions() {
+    for (name, bytes) in fixtures() {
+        let world = LOWorld::read_world(&mut Cursor::new(bytes)).unwrap();
+        assert_eq!(world.detect_version(), Some(LOFormatVersion::V1_0_13), "{}", name);
+    }
+
+    let bytes = fixture("string.world");
+    let (mut world, _) = round_trip(&bytes);
+    let LOStemContent::TileZoneMap { metadata, extra_fields, .. } = &mut world.stems[0].content else {
+        panic!("First stem is not a zone.");
+    };
+    metadata.fields.reverse();
+    metadata.fields.push(LOMetadataField::Int { name: "z".into(), default: 7, _unknown1: [0, 0] });
+    extra_fields.push(LOField::new(b"z", LOFieldValue::Int(8)));
+    assert_eq!(world.detect_version(), None);
+
+    world.set_version(LOFormatVersion::V1_0_13);
+    assert_eq!(world.detect_version(), Some(LOFormatVersion::V1_0_13));
+    // Stems of different versions.
+    let LOStemContent::TileMapEdit { metadata, .. } = &mut world.stems[1].content else {
+        panic!("Second stem is not a room.");
+    };
+    metadata.entries.pop();
+    assert_eq!(world.detect_version(), None);
+
+    // Converting back drops the unknown field and restores the original layout.
+    world.set_version(LOFormatVersion::V1_0_13);
+    let LOStemContent::TileZoneMap { extra_fields, .. } = &world.stems[0].content else {
+        panic!("First stem is not a zone.");
+    };
+    assert!(extra_fields.is_empty());
+    let mut output = Cursor::new(vec![]);
+    world.write_world(&mut output).unwrap();
+    assert_same_bytes("converted", &bytes, &output.into_inner());
+}
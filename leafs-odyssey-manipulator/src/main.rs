@@ -1,6 +1,5 @@
 mod room_title_commands;
 
-use binrw::BinRead;
 use std::{env, error::Error, path::PathBuf, process::exit};
 
 use leafs_odyssey_data::{data::*, io::get_worlds_folder};
@@ -33,7 +32,12 @@ fn main() -> Result<(), Box<dyn Error>> {
     
     println!("Reading world \"{:?}\"...", input_path);
     let mut fa = std::fs::File::open(input_path)?;
-    let mut world = LOWorld::read(&mut fa)?;
+    let mut world = LOWorld::read_world(&mut fa)?;
+
+    match world.detect_version() {
+        Some(version) => println!("World was saved by game version {}.", version),
+        None => println!("WARNING: World was saved by an unknown game version, it will be saved in the same layout."),
+    }
 
     println!("Applying modifications...");
     let modified = apply_world_commands(&mut world);
@@ -52,6 +56,18 @@ fn main() -> Result<(), Box<dyn Error>> {
         }
     }
 
+    for diagnostic in world.validate() {
+        println!("WARNING: {}", diagnostic);
+    }
+
+    if let Some(LOStemContent::TileZoneMap { room_info, .. }) = world.stems.first().map(|stem| &stem.content) {
+        for (i, room1) in room_info.iter().enumerate() {
+            for room2 in room_info[i + 1..].iter().filter(|room2| room1.overlaps(room2)) {
+                println!("WARNING: Rooms {} and {} overlap.", room1.id, room2.id);
+            }
+        }
+    }
+
     let output_name = String::from("generated_") + &input_name;
     let mut output_name = args.get(1).map(|arg| arg.clone()).unwrap_or(output_name);
     let output_path: PathBuf;
@@ -65,12 +81,9 @@ fn main() -> Result<(), Box<dyn Error>> {
     }
     println!("Writing file \"{:?}\"...", output_path);
 
-    unsafe {
-        let mut fa = std::fs::File::create(output_path)?;
+    let mut fa = std::fs::File::create(output_path)?;
 
-        let mut world = LOWorld::try_from(world)?;
-        world.write_world(&mut fa)?;
-    }
+    world.write_world(&mut fa)?;
 
     Ok(())
 }